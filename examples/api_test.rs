@@ -1,11 +1,13 @@
 use tracing::info;
 use walle::{
-    builtin::strip_prefix, handler_fn, new_walle, ActionCaller, AppConfig, Matcher,
-    MatcherHandlerExt, Matchers, MatchersConfig, PreHandler, ReplyAbleSession, Session,
+    builtin::{group_admin, strip_prefix, Arg, Args, Command, CommandArgs, ParsedArgs},
+    handler_fn, new_walle, ActionCaller, AppConfig, Matcher, MatcherHandlerExt, Matchers,
+    MatchersConfig, PreHandler, ReplyAbleSession, Session,
 };
 use walle_core::{
     event::{Group, Message, MessageDeatilTypes},
-    prelude::MsgSegment,
+    prelude::{MsgSegment, WalleError},
+    segment::Mention,
     value_map,
 };
 
@@ -16,6 +18,7 @@ async fn main() {
         .add_matcher(mute_test())
         .add_matcher(unmute_test())
         .add_matcher(member_test())
+        .add_matcher(get_test())
         .add_matcher(forward_test_plugin());
     let walle = new_walle(matchers);
     let joins = walle
@@ -48,49 +51,58 @@ fn recall_test_plugin() -> Matcher {
         .boxed()
 }
 
+#[derive(CommandArgs)]
+struct MuteArgs {
+    #[arg(help = "被禁言的用户")]
+    user: Mention,
+    #[arg(named, alias = "-t", default = "60s", help = "禁言时长")]
+    duration: std::time::Duration,
+}
+
 fn mute_test() -> Matcher {
-    strip_prefix("./mute")
-        .layer(handler_fn(|s: Session<Message, Group>| async move {
-            use walle_core::segment::MessageExt;
-            let mentions: Vec<walle_core::segment::Mention> = s.event.ty.message.clone().extract();
-            for mention in mentions {
+    MuteArgs::on_command(
+        "./mute",
+        handler_fn(
+            |s: Session<Message, MessageDeatilTypes>, group: Group, Args(args): Args<MuteArgs>| async move {
                 let r = s
                     .call_action(walle_core::action::Action {
                         action: "ban_group_member".to_string(),
                         selft: Some(s.event.ty.selft.clone()),
                         params: value_map! {
-                            "group_id": s.event.detail_type.group_id,
-                            "user_id": mention.user_id,
-                            "duration": 60
+                            "group_id": group.group_id,
+                            "user_id": args.user.user_id,
+                            "duration": args.duration.as_secs() as i64
                         },
                     })
                     .await;
                 println!("{:?}", r);
-            }
-        }))
-        .boxed()
+            },
+        ),
+    )
+    .with_async_permission(group_admin())
+    .boxed()
 }
 
 fn unmute_test() -> Matcher {
-    strip_prefix("./unmute")
-        .layer(handler_fn(|s: Session<Message, Group>| async move {
-            use walle_core::segment::MessageExt;
-            let mentions: Vec<walle_core::segment::Mention> = s.event.ty.message.clone().extract();
-            for mention in mentions {
+    Command::new("./unmute")
+        .arg(Arg::mention("user"))
+        .layer(handler_fn(
+            |s: Session<Message, MessageDeatilTypes>, group: Group, args: ParsedArgs| async move {
                 let r = s
                     .call_action(walle_core::action::Action {
                         action: "ban_group_member".to_string(),
                         selft: Some(s.event.ty.selft.clone()),
                         params: value_map! {
-                            "group_id": s.event.detail_type.group_id,
-                            "user_id": mention.user_id,
+                            "group_id": group.group_id,
+                            "user_id": args.mention("user").unwrap_or_default(),
                             "duration": 0
                         },
                     })
                     .await;
                 println!("{:?}", r);
-            }
-        }))
+            },
+        ))
+        .with_async_permission(group_admin())
         .boxed()
 }
 
@@ -112,6 +124,15 @@ fn member_test() -> Matcher {
         .boxed()
 }
 
+fn get_test() -> Matcher {
+    strip_prefix("./get")
+        .layer(handler_fn(|mut s: Session<Message, Group>| async move {
+            s.get("input message", None).await?;
+            Ok::<_, WalleError>(s.event.ty.message)
+        }))
+        .boxed()
+}
+
 // #[allow(dead_code)]
 // fn flash_test_plugin() -> Matcher {
 //     handler_fn(|s: Session<Message, MessageDeatilTypes>| async move {
@@ -1,9 +1,27 @@
-use walle::{builtin::echo, new_walle, MatcherHandlerExt, Matchers, MatchersConfig};
+use walle::{
+    builtin::{cancel, disable, echo, enable, help},
+    new_walle, MatcherHandlerExt, Matchers, MatchersConfig, SuperuserReporter, TracingReporter,
+};
 use walle_core::config::AppConfig;
 
 #[tokio::main]
 async fn main() {
-    let matchers = Matchers::default().add_matcher(echo().boxed());
+    let matchers = Matchers::default()
+        .add_matcher(
+            echo()
+                .boxed()
+                .name("echo")
+                .command("echo")
+                .description("复读消息")
+                .usage("echo <消息>")
+                .example("echo hello"),
+        )
+        .add_matcher(help())
+        .add_matcher(enable())
+        .add_matcher(disable())
+        .add_matcher(cancel())
+        .add_reporter(TracingReporter)
+        .add_reporter(SuperuserReporter);
     let walle = new_walle(matchers);
     let joins = walle
         .start(AppConfig::default(), MatchersConfig::default(), true)
@@ -0,0 +1,96 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Result};
+
+#[derive(Default)]
+struct ArgAttrs {
+    rename: Option<String>,
+    named: bool,
+    aliases: Vec<String>,
+    default: Option<String>,
+    help: Option<String>,
+}
+
+fn parse_attrs(attrs: &[syn::Attribute]) -> Result<ArgAttrs> {
+    let mut arg = ArgAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("arg")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("named") {
+                arg.named = true;
+            } else if meta.path.is_ident("rename") {
+                arg.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("alias") {
+                arg.aliases.push(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("default") {
+                arg.default = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("help") {
+                arg.help = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("unsupported arg attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(arg)
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    ident,
+                    "CommandArgs can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "CommandArgs can only be derived for structs",
+            ))
+        }
+    };
+    let mut args = vec![];
+    let mut values = vec![];
+    for field in fields {
+        let field_ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let attrs = parse_attrs(&field.attrs)?;
+        let name = attrs
+            .rename
+            .unwrap_or_else(|| field_ident.to_string().trim_start_matches("r#").to_string());
+        let mut arg = quote!(<#ty as ::walle::builtin::ArgType>::arg(#name));
+        if attrs.named {
+            arg = quote!(#arg.named());
+        }
+        for alias in &attrs.aliases {
+            arg = quote!(#arg.alias(#alias));
+        }
+        if let Some(default) = &attrs.default {
+            arg = quote!(#arg.default(#default));
+        }
+        if let Some(help) = &attrs.help {
+            arg = quote!(#arg.help(#help));
+        }
+        args.push(arg);
+        values.push(quote! {
+            #field_ident: <#ty as ::walle::builtin::ArgType>::from_value(args.get(#name))?
+        });
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::walle::builtin::CommandArgs for #ident #ty_generics #where_clause {
+            fn args() -> ::std::vec::Vec<::walle::builtin::Arg> {
+                ::std::vec![#(#args),*]
+            }
+            fn from_parsed(args: &::walle::builtin::ParsedArgs) -> ::std::option::Option<Self> {
+                ::std::option::Option::Some(Self {
+                    #(#values),*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,54 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, ItemFn};
+
+mod command_args;
+mod matcher;
+
+/// 为具名结构体实现 `CommandArgs`，字段类型须实现 `ArgType`
+///
+/// 字段属性 `#[arg(...)]`：
+/// - `rename = "name"` 参数名，默认为字段名
+/// - `named` 以 `--name value` 形式传入
+/// - `alias = "-n"` 具名参数别名，可重复
+/// - `default = "60s"` 默认值
+/// - `help = "..."` 帮助文本
+#[proc_macro_derive(CommandArgs, attributes(arg))]
+pub fn derive_command_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    command_args::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// 将 async fn 转换为返回 `Matcher` 的函数
+///
+/// 函数参数与 `handler_fn` 相同，返回值须实现 `IntoReply`，函数的文档注释作为 Matcher 描述
+///
+/// 属性：
+/// - `command = "cmd"` 匹配命令，遵循 `MatchersConfig` 中的命令起始符与分隔符
+/// - `alias = "..."` 命令别名，可重复
+/// - `name = "name"` Matcher 名称，默认为函数名
+/// - `priority = 10` 优先级
+/// - `permission = "group_admin"` 权限规则，须实现 `AsyncRule`，单个标识符时取自 `walle::builtin`
+/// - `concurrency = Concurrency::single_flight(..)` 并发限制
+/// - `cooldown = Cooldown::fixed_window(..)` 冷却配置，无权限的用户不消耗额度
+/// - `usage = "..."` 用法
+/// - `example = "..."` 示例，可重复
+///
+/// ```ignore
+/// /// 今日排行
+/// #[matcher(command = "waka今日排行", priority = 10)]
+/// async fn today_rank(s: Session<Message, MessageDeatilTypes>) -> Result<String, String> {
+///     ..
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn matcher(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut attrs = matcher::MatcherAttrs::default();
+    let parser = syn::meta::parser(|meta| attrs.parse(meta));
+    parse_macro_input!(attr with parser);
+    let item = parse_macro_input!(item as ItemFn);
+    matcher::expand(attrs, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,137 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{meta::ParseNestedMeta, Error, Expr, ItemFn, LitStr, Path, Result};
+
+#[derive(Default)]
+pub struct MatcherAttrs {
+    command: Option<LitStr>,
+    aliases: Vec<LitStr>,
+    name: Option<LitStr>,
+    priority: Option<Expr>,
+    permission: Option<Path>,
+    cooldown: Option<Expr>,
+    concurrency: Option<Expr>,
+    usage: Option<LitStr>,
+    examples: Vec<LitStr>,
+}
+
+impl MatcherAttrs {
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("command") {
+            self.command = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("alias") {
+            self.aliases.push(meta.value()?.parse()?);
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("priority") {
+            self.priority = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("permission") {
+            self.permission = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+        } else if meta.path.is_ident("cooldown") {
+            self.cooldown = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("concurrency") {
+            self.concurrency = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("usage") {
+            self.usage = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("example") {
+            self.examples.push(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error("unsupported matcher attribute"));
+        }
+        Ok(())
+    }
+}
+
+/// 函数上的文档注释，作为 Matcher 的描述
+fn description(item: &ItemFn) -> Option<String> {
+    let lines: Vec<String> = item
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let description = lines.join("\n").trim().to_string();
+    (!description.is_empty()).then_some(description)
+}
+
+pub fn expand(attrs: MatcherAttrs, item: ItemFn) -> Result<TokenStream> {
+    if item.sig.asyncness.is_none() {
+        return Err(Error::new_spanned(
+            item.sig.fn_token,
+            "#[matcher] can only be applied to async fn",
+        ));
+    }
+    if !item.sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &item.sig.generics,
+            "#[matcher] does not support generic fn",
+        ));
+    }
+    let vis = &item.vis;
+    let ident = &item.sig.ident;
+    let inner = format_ident!("__{}", ident);
+    let mut inner_item = item.clone();
+    inner_item.vis = syn::Visibility::Inherited;
+    inner_item.sig.ident = inner.clone();
+    inner_item.attrs.retain(|attr| !attr.path().is_ident("doc"));
+    let doc_attrs = item.attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+
+    let mut handler = quote!(::walle::handler_fn(#inner));
+    if let Some(command) = &attrs.command {
+        let aliases = &attrs.aliases;
+        handler = quote!(::walle::builtin::on_command(
+            ::walle::builtin::command_prefix(#command)#(.alias(#aliases))*,
+            #handler
+        ));
+    }
+    if let Some(concurrency) = &attrs.concurrency {
+        handler = quote!(::walle::MatcherHandlerExt::with_concurrency(#handler, #concurrency));
+    }
+    if let Some(cooldown) = &attrs.cooldown {
+        handler = quote!(::walle::MatcherHandlerExt::with_cooldown(#handler, #cooldown));
+    }
+    if let Some(permission) = &attrs.permission {
+        let rule = match permission.get_ident() {
+            Some(ident) => quote!(::walle::builtin::#ident()),
+            None => quote!(#permission()),
+        };
+        handler = quote!(::walle::MatcherHandlerExt::with_async_permission(#handler, #rule));
+    }
+    let name = attrs
+        .name
+        .map(|name| name.value())
+        .unwrap_or_else(|| ident.to_string());
+    let mut matcher = quote!(::walle::MatcherHandlerExt::boxed(#handler).name(#name));
+    if let Some(command) = &attrs.command {
+        let aliases = &attrs.aliases;
+        matcher = quote!(#matcher.command(#command)#(.command(#aliases))*);
+    }
+    if let Some(priority) = &attrs.priority {
+        matcher = quote!(#matcher.priority(#priority));
+    }
+    if let Some(description) = description(&item) {
+        matcher = quote!(#matcher.description(#description));
+    }
+    if let Some(usage) = &attrs.usage {
+        matcher = quote!(#matcher.usage(#usage));
+    }
+    for example in &attrs.examples {
+        matcher = quote!(#matcher.example(#example));
+    }
+    Ok(quote! {
+        #(#doc_attrs)*
+        #vis fn #ident() -> ::walle::Matcher {
+            #inner_item
+            #matcher
+        }
+    })
+}
@@ -1,11 +1,12 @@
+use std::time::Duration;
 use walle::{
-    builtin::{strip_prefix, strip_whitespace},
-    may_fail_handler_fn,
+    builtin::Token,
+    matcher,
     walle_core::{
         event::{Message, MessageDeatilTypes},
         util::ValueMapExt,
     },
-    Matcher, MatcherHandlerExt, PreHandler, ReplyAbleSession, Session,
+    ConversationError, Cooldown, LimitScope, Prompt, Session, Verdict,
 };
 
 mod data_source;
@@ -20,89 +21,97 @@ fn session_id(s: &Session<Message, MessageDeatilTypes>) -> String {
     }
 }
 
-pub fn set_api_key() -> Matcher {
-    strip_prefix("waka开卷")
-        .with(strip_whitespace())
-        .layer(may_fail_handler_fn(
-            |s: &Session<Message, MessageDeatilTypes>| {
-                Box::pin(async move {
-                    let mut data = users::load_users().await?;
-                    let map = data.entry(session_id(&s)).or_default();
-                    map.insert(
-                        s.event.extra.get_downcast("user_name").unwrap_or_default(), //todo
-                        s.event.ty.alt_message.clone(),
-                    );
-                    users::save_users(&data).await?;
-                    s.send("设置完毕，可以开始卷哩").await.ok();
-                    Ok::<_, String>(())
-                })
-            },
-        ))
-        .boxed()
+/// 设置 WakaTime API Key
+#[matcher(command = "waka开卷", usage = "waka开卷 <api_key>")]
+pub async fn set_api_key(
+    mut s: Session<Message, MessageDeatilTypes>,
+    tokens: Vec<Token>,
+) -> Result<&'static str, String> {
+    let api_key = match tokens.into_iter().next() {
+        Some(Token::Word(api_key)) => api_key,
+        _ => {
+            let prompt = Prompt::new("请发送你的 WakaTime API Key，发送“取消”以退出");
+            let answer = s.prompt_until(prompt, |s| {
+                let text = s.event.ty.alt_message.trim();
+                if text.starts_with("waka_") {
+                    Verdict::accept(text.to_string())
+                } else {
+                    Verdict::reject("API Key 应以 waka_ 开头，请重新发送")
+                }
+            });
+            match answer.await {
+                Ok(api_key) => api_key,
+                Err(ConversationError::Cancelled) => return Ok("已取消"),
+                Err(ConversationError::Timeout) => return Ok("等待超时，已取消"),
+                Err(ConversationError::RetriesExhausted) => return Ok("错误次数过多，已取消"),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    };
+    let mut data = users::load_users().await?;
+    let map = data.entry(session_id(&s)).or_default();
+    map.insert(
+        s.event.extra.get_downcast("user_name").unwrap_or_default(), //todo
+        api_key,
+    );
+    users::save_users(&data).await?;
+    Ok("设置完毕，可以开始卷哩")
 }
 
-pub fn today_rank() -> Matcher {
-    strip_prefix("waka今日排行")
-        .layer(may_fail_handler_fn(
-            |s: &Session<Message, MessageDeatilTypes>| {
-                Box::pin(async move {
-                    let data = users::load_users().await?;
-                    let api_keys = data.get(&session_id(&s)).ok_or("api_keys not found")?;
-                    let today = data_source::get_today(api_keys).await;
-                    let mut oks = String::from("今日排行: ");
-                    let mut errs = String::default();
-                    for (name, v) in today.iter() {
-                        match v {
-                            Ok(today) => {
-                                oks.push_str(&format!("\n{}: {}", name, today.data.digital))
-                            }
-                            Err(e) => {
-                                errs.push('\n');
-                                errs.push_str(e);
-                            }
-                        }
-                    }
-                    if !errs.is_empty() {
-                        oks.push_str("\nerrors:\n");
-                        oks.push_str(&errs);
-                    }
-                    s.send(oks).await.ok();
-                    Ok::<_, String>(())
-                })
-            },
-        ))
-        .boxed()
+/// 今日排行
+#[matcher(
+    command = "waka今日排行",
+    cooldown = Cooldown::fixed_window(1, Duration::from_secs(60))
+        .scope(LimitScope::Group)
+        .reply("排行榜刚刚查询过，请 {secs} 秒后再试")
+)]
+pub async fn today_rank(s: Session<Message, MessageDeatilTypes>) -> Result<String, String> {
+    let data = users::load_users().await?;
+    let api_keys = data.get(&session_id(&s)).ok_or("api_keys not found")?;
+    let today = data_source::get_today(api_keys).await;
+    let mut oks = String::from("今日排行: ");
+    let mut errs = String::default();
+    for (name, v) in today.iter() {
+        match v {
+            Ok(today) => oks.push_str(&format!("\n{}: {}", name, today.data.digital)),
+            Err(e) => {
+                errs.push('\n');
+                errs.push_str(e);
+            }
+        }
+    }
+    if !errs.is_empty() {
+        oks.push_str("\nerrors:\n");
+        oks.push_str(&errs);
+    }
+    Ok(oks)
 }
 
-pub fn weeks_rank() -> Matcher {
-    strip_prefix("waka本周排行")
-        .layer(may_fail_handler_fn(
-            |s: &Session<Message, MessageDeatilTypes>| {
-                Box::pin(async move {
-                    let data = users::load_users().await?;
-                    let api_keys = data.get(&session_id(&s)).ok_or("api_keys not found")?;
-                    let weeks = data_source::get_weekdays(api_keys).await;
-                    let mut oks = String::from("本周排行: ");
-                    let mut errs = String::default();
-                    for (name, v) in weeks.iter() {
-                        match v {
-                            Ok(v) => {
-                                oks.push_str(&format!("\n{}: {}h", name, v.total_seconds / 3600.0))
-                            }
-                            Err(e) => {
-                                errs.push('\n');
-                                errs.push_str(e);
-                            }
-                        }
-                    }
-                    if !errs.is_empty() {
-                        oks.push_str("\nerrors:\n");
-                        oks.push_str(&errs);
-                    }
-                    s.send(oks).await.ok();
-                    Ok::<_, String>(())
-                })
-            },
-        ))
-        .boxed()
+/// 本周排行
+#[matcher(
+    command = "waka本周排行",
+    cooldown = Cooldown::fixed_window(1, Duration::from_secs(60))
+        .scope(LimitScope::Group)
+        .reply("排行榜刚刚查询过，请 {secs} 秒后再试")
+)]
+pub async fn weeks_rank(s: Session<Message, MessageDeatilTypes>) -> Result<String, String> {
+    let data = users::load_users().await?;
+    let api_keys = data.get(&session_id(&s)).ok_or("api_keys not found")?;
+    let weeks = data_source::get_weekdays(api_keys).await;
+    let mut oks = String::from("本周排行: ");
+    let mut errs = String::default();
+    for (name, v) in weeks.iter() {
+        match v {
+            Ok(v) => oks.push_str(&format!("\n{}: {}h", name, v.total_seconds / 3600.0)),
+            Err(e) => {
+                errs.push('\n');
+                errs.push_str(e);
+            }
+        }
+    }
+    if !errs.is_empty() {
+        oks.push_str("\nerrors:\n");
+        oks.push_str(&errs);
+    }
+    Ok(oks)
 }
@@ -0,0 +1,165 @@
+use super::plain_text;
+use crate::{Matcher, MatcherHandler, MatcherHandlerExt, ReplyAbleSession, Rule, Session, Signal};
+use async_trait::async_trait;
+use walle_core::event::{Message, MessageDeatilTypes};
+
+/// 消息是否为 `MatchersConfig::cancel_keywords` 中的关键词
+pub(crate) fn is_cancel_keyword<D, S, P, I>(session: &Session<Message, D, S, P, I>) -> bool {
+    let text = plain_text(session);
+    session
+        .config
+        .cancel_keywords
+        .iter()
+        .any(|keyword| keyword == text.trim())
+}
+
+/// 消息为 `MatchersConfig::cancel_keywords` 中的关键词，且发送者存在等待中的对话
+///
+/// 在等待中的会话内发送的关键词由临时 Matcher 处理，不会到达此处
+pub struct CancelKeyword;
+
+impl<D, S, P, I> Rule<Message, D, S, P, I> for CancelKeyword {
+    fn rule(&self, session: &Session<Message, D, S, P, I>) -> Signal {
+        if is_cancel_keyword(session)
+            && !session
+                .matchers
+                .pending_waits(&session.event.ty.user_id)
+                .is_empty()
+        {
+            Signal::Matched
+        } else {
+            Signal::NotMatch
+        }
+    }
+}
+
+/// 取消发送者所有等待中的对话
+pub struct Cancel;
+
+#[async_trait]
+impl MatcherHandler<Message, MessageDeatilTypes> for Cancel {
+    async fn handle(&self, session: Session<Message, MessageDeatilTypes>) {
+        let count = session
+            .matchers
+            .cancel_waits(&session.event.ty.user_id)
+            .await;
+        let _ = session
+            .send(format!("已取消 {} 个等待中的对话", count))
+            .await;
+    }
+}
+
+/// 发送取消关键词时中止该用户在所有会话中等待的回复
+pub fn cancel() -> Matcher {
+    Cancel
+        .with_rule(CancelKeyword)
+        .boxed()
+        .name("cancel")
+        .description("取消所有等待中的对话")
+        .usage("取消")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, DummyCaller};
+    use crate::{handler_fn, MatchersConfig, MatchersHandle, WaitError};
+    use std::{sync::Arc, time::Duration};
+    use tokio::sync::mpsc;
+    use walle_core::event::Group;
+
+    /// 注册在群内等待回复的 Matcher 与全局取消 Matcher，返回等待结果的接收端
+    async fn setup(handle: &MatchersHandle) -> mpsc::UnboundedReceiver<bool> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        handle
+            .insert(
+                super::super::on_command(
+                    "ask",
+                    handler_fn(move |mut s: Session<Message, Group>| {
+                        let tx = tx.clone();
+                        async move {
+                            let r = s.get("?", Some(Duration::from_secs(5))).await;
+                            tx.send(matches!(r, Err(WaitError::Cancelled))).ok();
+                        }
+                    }),
+                )
+                .boxed()
+                .name("ask"),
+            )
+            .await;
+        handle.insert(cancel()).await;
+        rx
+    }
+
+    async fn wait_pending(handle: &MatchersHandle, count: usize) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while handle.pending_waits("u").len() < count {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    /// 在群 g 与 h 中各发起一次等待，再于 `group_id` 中发送 `text`，返回被取消的等待数与 bot 的回复
+    async fn cancelled_by(text: &str, group_id: Option<&str>) -> (usize, Vec<String>) {
+        let handle = MatchersHandle::default();
+        let mut rx = setup(&handle).await;
+        let (dummy, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        for group in ["g", "h"] {
+            handle
+                .call(
+                    &message_event(group, "u", Some(group), "ask"),
+                    &config,
+                    &caller,
+                )
+                .await;
+        }
+        wait_pending(&handle, 2).await;
+        handle
+            .call(&message_event("2", "u", group_id, text), &config, &caller)
+            .await;
+        let mut cancelled = 0;
+        for _ in 0..2 {
+            if tokio::time::timeout(Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap()
+            {
+                cancelled += 1;
+            }
+        }
+        assert!(handle.pending_waits("u").is_empty());
+        // 等待取消 Matcher 发送回复
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let replies = dummy
+            .actions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|action| {
+                action
+                    .params
+                    .get("message")
+                    .map(|m| format!("{:?}", m))
+                    .unwrap_or_default()
+            })
+            .collect();
+        (cancelled, replies)
+    }
+
+    #[tokio::test]
+    async fn cancel_in_waiting_scope() {
+        let (cancelled, replies) = cancelled_by("取消", Some("g")).await;
+        assert_eq!(cancelled, 2);
+        assert!(!replies.iter().any(|reply| reply.contains("已取消")));
+    }
+
+    #[tokio::test]
+    async fn cancel_from_other_scope() {
+        let (cancelled, replies) = cancelled_by("取消", None).await;
+        assert_eq!(cancelled, 2);
+        assert!(replies.iter().any(|reply| reply.contains("已取消 2 个")));
+    }
+}
@@ -0,0 +1,961 @@
+use super::{command_prefix, CommandPrefix};
+use crate::{
+    layered_pre_handle, pre_handle_fn, FromSession, MatcherHandler, PreHandler, ReplyAbleSession,
+    Session, Signal,
+};
+use std::{collections::HashMap, fmt, future::Future, pin::Pin, time::Duration};
+use walle_core::{
+    event::Message,
+    prelude::{async_trait, MsgSegment},
+    segment::{Image, Mention, Segments},
+    util::ValueMapExt,
+};
+
+pub use walle_macros::CommandArgs;
+
+/// 命令参数词元
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Word(String),
+    Mention(Mention),
+    Image(Image),
+    /// 其他消息段
+    Segment(MsgSegment),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "{}", word),
+            Self::Mention(mention) => write!(f, "@{}", mention.user_id),
+            Self::Image(_) => write!(f, "[图片]"),
+            Self::Segment(seg) => write!(f, "{}", seg.alt()),
+        }
+    }
+}
+
+/// 按 shell 风格切分文本，支持单双引号与 `\` 转义，全角空格同样视为分隔符
+pub fn shell_split(text: &str) -> Vec<String> {
+    split_words(text)
+        .into_iter()
+        .map(|(_, word)| word)
+        .collect()
+}
+
+/// `shell_split` 的切分结果及各词在原文中的起始字节偏移
+fn split_words(text: &str) -> Vec<(usize, String)> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut start = None;
+    let mut quote: Option<char> = None;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                word.extend(chars.next().map(|(_, c)| c));
+                start.get_or_insert(i);
+            }
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                start.get_or_insert(i);
+            }
+            (None, c) if c.is_whitespace() => {
+                if let Some(start) = start.take() {
+                    words.push((start, std::mem::take(&mut word)));
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(start) = start {
+        words.push((start, word));
+    }
+    words
+}
+
+fn segment_token(seg: &MsgSegment) -> Token {
+    match seg.ty.as_str() {
+        "mention" => match seg.data.get_downcast("user_id") {
+            Ok(user_id) => Token::Mention(Mention { user_id }),
+            Err(_) => Token::Segment(seg.clone()),
+        },
+        "image" => match seg.data.get_downcast("file_id") {
+            Ok(file_id) => Token::Image(Image { file_id }),
+            Err(_) => Token::Segment(seg.clone()),
+        },
+        _ => Token::Segment(seg.clone()),
+    }
+}
+
+/// 词元及其起始位置：所在消息段的下标与文本段内的字节偏移
+fn tokenize_spans(segments: &Segments) -> Vec<(Token, usize, usize)> {
+    let mut tokens = vec![];
+    for (index, seg) in segments.iter().enumerate() {
+        if seg.ty.as_str() == "text" {
+            let text: String = seg.data.get_downcast("text").unwrap_or_default();
+            tokens.extend(
+                split_words(&text)
+                    .into_iter()
+                    .map(|(offset, word)| (Token::Word(word), index, offset)),
+            );
+        } else {
+            tokens.push((segment_token(seg), index, 0));
+        }
+    }
+    tokens
+}
+
+/// 将消息段切分为词元，文本段按 `shell_split` 切分，其他消息段各自成为一个词元
+pub fn tokenize(segments: &Segments) -> Vec<Token> {
+    tokenize_spans(segments)
+        .into_iter()
+        .map(|(token, ..)| token)
+        .collect()
+}
+
+/// 自指定位置起的原始消息文本，非文本消息段以词元形式表示
+fn rest_text(segments: &Segments, index: usize, offset: usize) -> String {
+    let text: String = segments[index..]
+        .iter()
+        .enumerate()
+        .map(|(i, seg)| match seg.ty.as_str() {
+            "text" => {
+                let text: String = seg.data.get_downcast("text").unwrap_or_default();
+                if i == 0 {
+                    text[offset..].to_string()
+                } else {
+                    text
+                }
+            }
+            _ => segment_token(seg).to_string(),
+        })
+        .collect();
+    text.trim_end().to_string()
+}
+
+/// 切分当前消息并将 `Vec<Token>` 存入 Session，总是返回 `Signal::Matched`
+pub fn tokenize_message<D, S, P, I>() -> impl PreHandler<Message, D, S, P, I> {
+    pre_handle_fn(|session: &mut Session<Message, D, S, P, I>| {
+        let tokens = tokenize(session.message());
+        session.extensions.insert(tokens);
+        Signal::Matched
+    })
+}
+
+/// 优先使用 Session 中已存储的词元
+fn session_tokens<D, S, P, I>(session: &Session<Message, D, S, P, I>) -> Vec<Token> {
+    session
+        .extensions
+        .get::<Vec<Token>>()
+        .cloned()
+        .unwrap_or_else(|| tokenize(session.message()))
+}
+
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for Vec<Token> {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        Some(session_tokens(session))
+    }
+}
+
+/// 解析时长，如 `90`、`30s`、`5m`、`1h30m`、`2d`，无单位时为秒
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    if s.is_empty() {
+        return None;
+    }
+    if let Ok(secs) = s.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' | '秒' => 1,
+            'm' | '分' => 60,
+            'h' | '时' => 60 * 60,
+            'd' | '天' => 24 * 60 * 60,
+            _ => return None,
+        };
+        total = number
+            .parse::<u64>()
+            .ok()?
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))?;
+        number.clear();
+    }
+    number.is_empty().then(|| Duration::from_secs(total))
+}
+
+/// 参数类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgKind {
+    Int,
+    Duration,
+    Mention,
+    Image,
+    /// 单个词
+    Text,
+    /// 可选值之一
+    Enum(Vec<String>),
+    /// 剩余全部内容
+    Rest,
+}
+
+impl fmt::Display for ArgKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int => write!(f, "整数"),
+            Self::Duration => write!(f, "时长"),
+            Self::Mention => write!(f, "@用户"),
+            Self::Image => write!(f, "图片"),
+            Self::Text => write!(f, "文本"),
+            Self::Enum(choices) => write!(f, "{}", choices.join("|")),
+            Self::Rest => write!(f, "文本..."),
+        }
+    }
+}
+
+/// 已解析的参数值
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Int(i64),
+    Duration(Duration),
+    Mention(String),
+    Image(Image),
+    Text(String),
+}
+
+/// 参数声明
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub name: String,
+    pub kind: ArgKind,
+    pub required: bool,
+    /// 以 `--name value` 形式传入
+    pub named: bool,
+    pub aliases: Vec<String>,
+    pub default: Option<String>,
+    pub help: String,
+}
+
+impl Arg {
+    pub fn new(name: &str, kind: ArgKind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            required: true,
+            named: false,
+            aliases: vec![],
+            default: None,
+            help: String::default(),
+        }
+    }
+    pub fn int(name: &str) -> Self {
+        Self::new(name, ArgKind::Int)
+    }
+    pub fn duration(name: &str) -> Self {
+        Self::new(name, ArgKind::Duration)
+    }
+    pub fn mention(name: &str) -> Self {
+        Self::new(name, ArgKind::Mention)
+    }
+    pub fn image(name: &str) -> Self {
+        Self::new(name, ArgKind::Image)
+    }
+    pub fn text(name: &str) -> Self {
+        Self::new(name, ArgKind::Text)
+    }
+    pub fn enumeration(name: &str, choices: &[&str]) -> Self {
+        Self::new(
+            name,
+            ArgKind::Enum(choices.iter().map(ToString::to_string).collect()),
+        )
+    }
+    pub fn rest(name: &str) -> Self {
+        Self::new(name, ArgKind::Rest)
+    }
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+    pub fn named(mut self) -> Self {
+        self.named = true;
+        self
+    }
+    /// 具名参数的别名，如 `-t`
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+    /// 默认值，参数缺省时按参数类型解析
+    pub fn default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_string());
+        self.required = false;
+        self
+    }
+    pub fn help(mut self, help: &str) -> Self {
+        self.help = help.to_string();
+        self
+    }
+    fn matches_flag(&self, flag: &str) -> bool {
+        flag.strip_prefix("--") == Some(self.name.as_str())
+            || self.aliases.iter().any(|alias| alias == flag)
+    }
+    fn parse_word(&self, word: &str) -> Option<ArgValue> {
+        match &self.kind {
+            ArgKind::Int => word.parse().ok().map(ArgValue::Int),
+            ArgKind::Duration => parse_duration(word).map(ArgValue::Duration),
+            ArgKind::Text | ArgKind::Rest => Some(ArgValue::Text(word.to_string())),
+            ArgKind::Enum(choices) => choices
+                .iter()
+                .any(|choice| choice == word)
+                .then(|| ArgValue::Text(word.to_string())),
+            ArgKind::Mention | ArgKind::Image => None,
+        }
+    }
+    pub(crate) fn parse_token(&self, token: &Token) -> Result<ArgValue, ArgError> {
+        let value = match (&self.kind, token) {
+            (ArgKind::Mention, Token::Mention(mention)) => {
+                Some(ArgValue::Mention(mention.user_id.clone()))
+            }
+            (ArgKind::Image, Token::Image(image)) => Some(ArgValue::Image(image.clone())),
+            (_, Token::Word(word)) => self.parse_word(word),
+            _ => None,
+        };
+        value.ok_or_else(|| ArgError::Invalid {
+            name: self.name.clone(),
+            kind: self.kind.clone(),
+            value: token.to_string(),
+        })
+    }
+    fn usage(&self) -> String {
+        let mut usage = if self.named {
+            format!("--{} <{}>", self.name, self.kind)
+        } else {
+            format!("{}:{}", self.name, self.kind)
+        };
+        if let Some(default) = &self.default {
+            usage = format!("{}={}", usage, default);
+        }
+        match (self.required, self.named) {
+            (true, true) => usage,
+            (true, false) => format!("<{}>", usage),
+            (false, _) => format!("[{}]", usage),
+        }
+    }
+}
+
+/// 参数解析错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgError {
+    Missing(String),
+    Invalid {
+        name: String,
+        kind: ArgKind,
+        value: String,
+    },
+    Unexpected(String),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "缺少参数 {}", name),
+            Self::Invalid { name, kind, value } => {
+                write!(f, "参数 {} 应为{}，而不是 {}", name, kind, value)
+            }
+            Self::Unexpected(value) => write!(f, "多余的参数 {}", value),
+        }
+    }
+}
+
+/// 已解析的命令参数
+#[derive(Debug, Clone, Default)]
+pub struct ParsedArgs(pub HashMap<String, ArgValue>);
+
+impl ParsedArgs {
+    pub fn get(&self, name: &str) -> Option<&ArgValue> {
+        self.0.get(name)
+    }
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.get(name) {
+            Some(ArgValue::Int(i)) => Some(*i),
+            _ => None,
+        }
+    }
+    pub fn duration(&self, name: &str) -> Option<Duration> {
+        match self.get(name) {
+            Some(ArgValue::Duration(d)) => Some(*d),
+            _ => None,
+        }
+    }
+    pub fn mention(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(ArgValue::Mention(user_id)) => Some(user_id),
+            _ => None,
+        }
+    }
+    pub fn image(&self, name: &str) -> Option<&Image> {
+        match self.get(name) {
+            Some(ArgValue::Image(image)) => Some(image),
+            _ => None,
+        }
+    }
+    /// 文本、枚举与剩余内容参数
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(ArgValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// 由 CommandLayer 或 SegmentPattern 解析后提取，否则提取失败
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for ParsedArgs {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        session.extensions.get::<Self>().cloned()
+    }
+}
+
+/// 命令声明
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub args: Vec<Arg>,
+}
+
+impl Command {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            aliases: vec![],
+            args: vec![],
+        }
+    }
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+    fn prefix(&self) -> CommandPrefix {
+        self.aliases
+            .iter()
+            .fold(command_prefix(&self.name), |prefix, alias| {
+                prefix.alias(alias)
+            })
+    }
+    pub fn arg(mut self, arg: Arg) -> Self {
+        self.args.push(arg);
+        self
+    }
+    /// 生成用法，如 `mute <user:@用户> [duration:时长=60s]`
+    pub fn usage(&self) -> String {
+        let mut usage = vec![self.name.clone()];
+        usage.extend(self.args.iter().map(Arg::usage));
+        usage.join(" ")
+    }
+    /// 用法及各参数的帮助文本
+    pub fn help(&self) -> String {
+        let mut lines = vec![format!("用法：{}", self.usage())];
+        if !self.aliases.is_empty() {
+            lines.push(format!("别名：{}", self.aliases.join(" ")));
+        }
+        lines.extend(
+            self.args
+                .iter()
+                .filter(|arg| !arg.help.is_empty())
+                .map(|arg| format!("  {}: {}", arg.name, arg.help)),
+        );
+        lines.join("\n")
+    }
+    /// 解析词元，剩余参数为以空格连接的其余词元，需要保留原文时使用 `parse_message`
+    pub fn parse(&self, tokens: &[Token]) -> Result<ParsedArgs, ArgError> {
+        self.parse_with(tokens, |i| {
+            tokens[i..]
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+    /// 解析消息，剩余参数保留原文中的空白与引号
+    pub fn parse_message(&self, segments: &Segments) -> Result<ParsedArgs, ArgError> {
+        let spans = tokenize_spans(segments);
+        let tokens: Vec<Token> = spans.iter().map(|(token, ..)| token.clone()).collect();
+        self.parse_with(&tokens, |i| rest_text(segments, spans[i].1, spans[i].2))
+    }
+    /// `rest(i)` 返回自第 i 个词元起的剩余参数
+    fn parse_with<F>(&self, tokens: &[Token], rest: F) -> Result<ParsedArgs, ArgError>
+    where
+        F: Fn(usize) -> String,
+    {
+        let mut parsed = HashMap::new();
+        let mut positionals = self.args.iter().filter(|arg| !arg.named);
+        let mut tokens = tokens.iter().enumerate();
+        while let Some((i, token)) = tokens.next() {
+            if let Token::Word(word) = token {
+                if let Some(arg) = self
+                    .args
+                    .iter()
+                    .find(|arg| arg.named && arg.matches_flag(word))
+                {
+                    let (_, value) = tokens
+                        .next()
+                        .ok_or_else(|| ArgError::Missing(arg.name.clone()))?;
+                    parsed.insert(arg.name.clone(), arg.parse_token(value)?);
+                    continue;
+                }
+            }
+            let arg = positionals
+                .next()
+                .ok_or_else(|| ArgError::Unexpected(token.to_string()))?;
+            if arg.kind == ArgKind::Rest {
+                parsed.insert(arg.name.clone(), ArgValue::Text(rest(i)));
+                break;
+            }
+            parsed.insert(arg.name.clone(), arg.parse_token(token)?);
+        }
+        for arg in &self.args {
+            if parsed.contains_key(&arg.name) {
+                continue;
+            }
+            if let Some(default) = &arg.default {
+                let value = arg.parse_word(default).ok_or_else(|| ArgError::Invalid {
+                    name: arg.name.clone(),
+                    kind: arg.kind.clone(),
+                    value: default.clone(),
+                })?;
+                parsed.insert(arg.name.clone(), value);
+            } else if arg.required {
+                return Err(ArgError::Missing(arg.name.clone()));
+            }
+        }
+        Ok(ParsedArgs(parsed))
+    }
+    /// 匹配命令并解析参数，解析失败时回复错误与用法
+    pub fn layer<H>(self, handler: H) -> CommandLayer<H> {
+        CommandLayer {
+            command: self,
+            handler,
+        }
+    }
+}
+
+pub struct CommandLayer<H> {
+    pub command: Command,
+    pub handler: H,
+}
+
+#[async_trait]
+impl<H, D, S, P, I> MatcherHandler<Message, D, S, P, I> for CommandLayer<H>
+where
+    H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send + Sync + 'static,
+    S: Send + Sync + 'static,
+    P: Send + Sync + 'static,
+    I: Send + Sync + 'static,
+    Session<Message, D, S, P, I>: ReplyAbleSession,
+{
+    async fn pre_handle(&self, session: &mut Session<Message, D, S, P, I>) -> Signal {
+        super::strip_whitespace().pre_handle(session);
+        if self.command.prefix().pre_handle(session) == Signal::NotMatch {
+            return Signal::NotMatch;
+        }
+        let parsed = self.command.parse_message(session.message());
+        session.extensions.insert(tokenize(session.message()));
+        // 解析失败时仍需内层 Handler 匹配（如权限层），才会回复错误与用法
+        match parsed {
+            Ok(args) => session.extensions.insert(args),
+            Err(e) => session.extensions.insert(e),
+        }
+        layered_pre_handle(&self.handler, session).await
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<Message, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<Message, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        if session.extensions.get::<ArgError>().is_some() {
+            Box::pin(async { Signal::Matched })
+        } else {
+            self.handler.prepare(session)
+        }
+    }
+    async fn handle(&self, session: Session<Message, D, S, P, I>) {
+        match session.extensions.get::<ArgError>() {
+            Some(e) => {
+                let reply = format!("{}\n{}", e, self.command.help());
+                let _ = session.send(reply).await;
+            }
+            None => self.handler.handle(session).await,
+        }
+    }
+}
+
+/// 可由命令参数构造的类型
+pub trait CommandArgs: Sized {
+    fn args() -> Vec<Arg>;
+    fn from_parsed(args: &ParsedArgs) -> Option<Self>;
+    /// 以 Self 的参数声明构造命令
+    fn command(name: &str) -> Command {
+        Command {
+            args: Self::args(),
+            ..Command::new(name)
+        }
+    }
+    /// 匹配命令并按 Self 的声明解析参数，无需指定 Handler 类型
+    ///
+    /// `MuteArgs::on_command("mute", handler)`
+    fn on_command<H>(name: &str, handler: H) -> CommandLayer<H> {
+        Self::command(name).layer(handler)
+    }
+}
+
+/// 可作为命令参数的类型
+pub trait ArgType: Sized {
+    fn arg(name: &str) -> Arg;
+    fn from_value(value: Option<&ArgValue>) -> Option<Self>;
+}
+
+macro_rules! impl_int_arg {
+    ($($ty: ty),*) => {
+        $(
+            impl ArgType for $ty {
+                fn arg(name: &str) -> Arg {
+                    Arg::int(name)
+                }
+                fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+                    match value {
+                        Some(ArgValue::Int(i)) => (*i).try_into().ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_int_arg!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl ArgType for Duration {
+    fn arg(name: &str) -> Arg {
+        Arg::duration(name)
+    }
+    fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+        match value {
+            Some(ArgValue::Duration(d)) => Some(*d),
+            _ => None,
+        }
+    }
+}
+
+impl ArgType for Mention {
+    fn arg(name: &str) -> Arg {
+        Arg::mention(name)
+    }
+    fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+        match value {
+            Some(ArgValue::Mention(user_id)) => Some(Mention {
+                user_id: user_id.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl ArgType for Image {
+    fn arg(name: &str) -> Arg {
+        Arg::image(name)
+    }
+    fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+        match value {
+            Some(ArgValue::Image(image)) => Some(image.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl ArgType for String {
+    fn arg(name: &str) -> Arg {
+        Arg::text(name)
+    }
+    fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+        match value {
+            Some(ArgValue::Text(text)) => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// 剩余全部内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rest(pub String);
+
+impl ArgType for Rest {
+    fn arg(name: &str) -> Arg {
+        Arg::rest(name)
+    }
+    fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+        String::from_value(value).map(Self)
+    }
+}
+
+/// 可选参数
+impl<A: ArgType> ArgType for Option<A> {
+    fn arg(name: &str) -> Arg {
+        A::arg(name).optional()
+    }
+    fn from_value(value: Option<&ArgValue>) -> Option<Self> {
+        match value {
+            Some(_) => A::from_value(value).map(Some),
+            None => Some(None),
+        }
+    }
+}
+
+macro_rules! impl_command_args {
+    ($($idx: tt $ty: ident),+) => {
+        /// 按顺序声明为位置参数 `arg0`、`arg1`...
+        impl<$($ty),+> CommandArgs for ($($ty,)+)
+        where
+            $($ty: ArgType,)+
+        {
+            fn args() -> Vec<Arg> {
+                vec![$($ty::arg(concat!("arg", $idx)),)+]
+            }
+            fn from_parsed(args: &ParsedArgs) -> Option<Self> {
+                Some(($($ty::from_value(args.get(concat!("arg", $idx)))?,)+))
+            }
+        }
+    };
+}
+
+impl_command_args!(0 A0);
+impl_command_args!(0 A0, 1 A1);
+impl_command_args!(0 A0, 1 A1, 2 A2);
+impl_command_args!(0 A0, 1 A1, 2 A2, 3 A3);
+impl_command_args!(0 A0, 1 A1, 2 A2, 3 A3, 4 A4);
+impl_command_args!(0 A0, 1 A1, 2 A2, 3 A3, 4 A4, 5 A5);
+
+/// 命令参数，优先使用 CommandLayer 的解析结果，否则解析剩余消息，解析失败时提取失败
+///
+/// `handler_fn(|s: Session<Message, Group>, Args((user, minutes)): Args<(Mention, u64)>| ..)`
+#[derive(Debug, Clone)]
+pub struct Args<A>(pub A);
+
+impl<A, D, S, P, I> FromSession<Message, D, S, P, I> for Args<A>
+where
+    A: CommandArgs,
+{
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        match session.extensions.get::<ParsedArgs>() {
+            Some(parsed) => A::from_parsed(parsed),
+            None => A::from_parsed(&A::command("").parse_message(session.message()).ok()?),
+        }
+        .map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2天"), Some(Duration::from_secs(2 * 86400)));
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("5m3"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn shell_split_quotes_and_escapes() {
+        assert_eq!(shell_split("a  b\u{3000}c"), ["a", "b", "c"]);
+        assert_eq!(
+            shell_split(r#"say "hello world" 'it''s' a\ b"#),
+            ["say", "hello world", "its", "a b"]
+        );
+        assert_eq!(shell_split(r#""" x"#), ["", "x"]);
+        assert_eq!(
+            split_words("ab  \"c d\""),
+            [(0, "ab".to_string()), (4, "c d".to_string())]
+        );
+    }
+
+    fn mute() -> Command {
+        Command::new("mute")
+            .arg(Arg::mention("user"))
+            .arg(Arg::duration("time").named().alias("-t").default("60s"))
+            .arg(Arg::rest("reason").optional())
+    }
+
+    fn text(text: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "text".to_string(),
+            data: walle_core::value_map! {"text": text},
+        }
+    }
+
+    fn mention(user_id: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "mention".to_string(),
+            data: walle_core::value_map! {"user_id": user_id},
+        }
+    }
+
+    #[test]
+    fn command_parse() {
+        let segments = vec![mention("u"), text(" -t 5m 刷屏  太多")];
+        let parsed = mute().parse(&tokenize(&segments)).unwrap();
+        assert_eq!(
+            parsed.get("user"),
+            Some(&ArgValue::Mention("u".to_string()))
+        );
+        assert_eq!(
+            parsed.get("time"),
+            Some(&ArgValue::Duration(Duration::from_secs(300)))
+        );
+        assert_eq!(
+            parsed.get("reason"),
+            Some(&ArgValue::Text("刷屏 太多".to_string()))
+        );
+
+        let parsed = mute().parse(&tokenize(&vec![mention("u")])).unwrap();
+        assert_eq!(
+            parsed.get("time"),
+            Some(&ArgValue::Duration(Duration::from_secs(60)))
+        );
+        assert_eq!(parsed.get("reason"), None);
+
+        assert!(matches!(
+            mute().parse(&tokenize(&vec![mention("u"), text(" -t soon")])),
+            Err(ArgError::Invalid { .. })
+        ));
+        assert!(matches!(
+            Command::new("x").parse(&tokenize(&vec![text("extra")])),
+            Err(ArgError::Unexpected(_))
+        ));
+        assert!(matches!(
+            mute().parse(&tokenize(&vec![mention("u"), text(" -t")])),
+            Err(ArgError::Missing(_))
+        ));
+    }
+
+    #[test]
+    fn rest_keeps_original_text() {
+        let segments = vec![
+            mention("u"),
+            text(" 刷屏  \"太多\"  "),
+            mention("v"),
+            text(" 了 "),
+        ];
+        let parsed = mute().parse_message(&segments).unwrap();
+        assert_eq!(
+            parsed.get("reason"),
+            Some(&ArgValue::Text("刷屏  \"太多\"  @v 了".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_duration_overflow() {
+        assert_eq!(parse_duration("18446744073709551615d"), None);
+        assert_eq!(parse_duration("18446744073709551615s1s"), None);
+    }
+
+    #[tokio::test]
+    async fn arg_error_requires_inner_match() {
+        use crate::testing::{message_event, session};
+        use crate::{handler_fn, rule_fn, Rule};
+        use walle_core::event::Private;
+
+        for (signal, expected) in [
+            (Signal::NotMatch, Signal::NotMatch),
+            (Signal::Matched, Signal::Matched),
+        ] {
+            let layer = mute().layer(
+                rule_fn(move |_: &Session<Message, Private>| signal)
+                    .layer(handler_fn(|_: Session<Message, Private>| async {})),
+            );
+            let mut session = session(message_event("1", "u", None, "mute"));
+            assert_eq!(layer.pre_handle(&mut session).await, expected);
+            assert!(session.extensions.get::<ArgError>().is_some());
+        }
+    }
+
+    #[derive(CommandArgs)]
+    struct MuteArgs {
+        user: Mention,
+        #[arg(
+            rename = "time",
+            named,
+            alias = "-t",
+            alias = "--time",
+            default = "60s"
+        )]
+        duration: Duration,
+        #[arg(help = "禁言原因")]
+        reason: Option<Rest>,
+    }
+
+    #[test]
+    fn derived_args_declaration() {
+        let args = MuteArgs::args();
+        let names: Vec<&str> = args.iter().map(|arg| arg.name.as_str()).collect();
+        assert_eq!(names, ["user", "time", "reason"]);
+        assert_eq!(args[0].kind, ArgKind::Mention);
+        assert!(args[0].required);
+        assert!(args[1].named);
+        assert_eq!(args[1].aliases, ["-t", "--time"]);
+        assert_eq!(args[1].default.as_deref(), Some("60s"));
+        assert_eq!(args[2].kind, ArgKind::Rest);
+        assert!(!args[2].required);
+        assert_eq!(args[2].help, "禁言原因");
+    }
+
+    #[test]
+    fn derived_args_parse() {
+        let command = MuteArgs::command("mute");
+        let parsed = command
+            .parse(&tokenize(&vec![mention("u"), text(" --time 5m 刷屏")]))
+            .unwrap();
+        let args = MuteArgs::from_parsed(&parsed).unwrap();
+        assert_eq!(args.user.user_id, "u");
+        assert_eq!(args.duration, Duration::from_secs(300));
+        assert_eq!(args.reason, Some(Rest("刷屏".to_string())));
+
+        let parsed = command.parse(&tokenize(&vec![mention("u")])).unwrap();
+        let args = MuteArgs::from_parsed(&parsed).unwrap();
+        assert_eq!(args.duration, Duration::from_secs(60));
+        assert_eq!(args.reason, None);
+
+        assert!(matches!(
+            command.parse(&tokenize(&vec![text("-t 5m")])),
+            Err(ArgError::Missing(_))
+        ));
+    }
+}
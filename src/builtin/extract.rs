@@ -0,0 +1,77 @@
+use super::plain_text;
+use crate::{FromSession, Session};
+use walle_core::{
+    event::{Group, Message, MessageDeatilTypes},
+    segment::{Image, Mention, MessageExt},
+    util::ValueMapExt,
+};
+
+/// 消息中所有文本段拼接后去除首尾空白的文本
+#[derive(Debug, Clone)]
+pub struct Text(pub String);
+
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for Text {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        Some(Self(plain_text(session).trim().to_string()))
+    }
+}
+
+/// 消息中提及的用户 user_id，不存在时提取失败
+#[derive(Debug, Clone)]
+pub struct Mentions(pub Vec<String>);
+
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for Mentions {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        let users: Vec<String> = session
+            .message()
+            .clone()
+            .extract::<Mention>()
+            .into_iter()
+            .map(|mention| mention.user_id)
+            .collect();
+        (!users.is_empty()).then_some(Self(users))
+    }
+}
+
+/// 消息中的图片，不存在时提取失败
+#[derive(Debug, Clone)]
+pub struct Images(pub Vec<Image>);
+
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for Images {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        let images = session.message().clone().extract::<Image>();
+        (!images.is_empty()).then_some(Self(images))
+    }
+}
+
+/// 消息发送者
+#[derive(Debug, Clone)]
+pub struct Sender {
+    pub user_id: String,
+    pub user_name: Option<String>,
+}
+
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for Sender {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        Some(Self {
+            user_id: session.event.ty.user_id.clone(),
+            user_name: session.event.extra.get_downcast("user_name").ok(),
+        })
+    }
+}
+
+impl<T, S, P, I> FromSession<T, Group, S, P, I> for Group {
+    fn from_session(session: &Session<T, Group, S, P, I>) -> Option<Self> {
+        Some(session.event.detail_type.clone())
+    }
+}
+
+/// 私聊消息中提取失败
+impl<T, S, P, I> FromSession<T, MessageDeatilTypes, S, P, I> for Group {
+    fn from_session(session: &Session<T, MessageDeatilTypes, S, P, I>) -> Option<Self> {
+        match &session.event.detail_type {
+            MessageDeatilTypes::Group(group) => Some(group.clone()),
+            MessageDeatilTypes::Private(_) => None,
+        }
+    }
+}
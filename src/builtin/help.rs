@@ -0,0 +1,138 @@
+use super::on_command;
+use crate::{
+    Matcher, MatcherHandler, MatcherHandlerExt, MatcherMetadata, MatchersConfig, ReplyAbleSession,
+    Session,
+};
+use async_trait::async_trait;
+use walle_core::event::{Event, Message, MessageDeatilTypes};
+
+pub struct Help;
+
+/// 以首个命令起始符渲染的命令名，没有命令的 Matcher 使用 Matcher 名
+fn command_label(metadata: &MatcherMetadata, config: &MatchersConfig) -> String {
+    match metadata.commands.first() {
+        Some(command) => format!(
+            "{}{}",
+            config
+                .command_start
+                .first()
+                .map(String::as_str)
+                .unwrap_or_default(),
+            command
+        ),
+        None => metadata.name.clone().unwrap_or_default(),
+    }
+}
+
+/// `help <命令>` 中的命令是否指向该 Matcher，命令可省略或带任一命令起始符
+fn is_command(metadata: &MatcherMetadata, query: &str, config: &MatchersConfig) -> bool {
+    if metadata.commands.is_empty() {
+        return metadata.name.as_deref() == Some(query);
+    }
+    let starts = config.command_start.iter().map(String::as_str);
+    std::iter::once("").chain(starts).any(|start| {
+        query
+            .strip_prefix(start)
+            .is_some_and(|query| metadata.commands.iter().any(|command| command == query))
+    })
+}
+
+fn format_list(metadatas: &[MatcherMetadata], config: &MatchersConfig) -> String {
+    let mut lines = vec!["可用命令：".to_string()];
+    for metadata in metadatas {
+        let name = command_label(metadata, config);
+        if metadata.description.is_empty() {
+            lines.push(name.to_string());
+        } else {
+            lines.push(format!("{}: {}", name, metadata.description));
+        }
+    }
+    lines.push("使用 help <命令> 查看命令用法".to_string());
+    lines.join("\n")
+}
+
+fn format_detail(metadata: &MatcherMetadata, config: &MatchersConfig) -> String {
+    let mut lines = vec![command_label(metadata, config)];
+    if metadata.commands.len() > 1 {
+        lines.push(format!("别名：{}", metadata.commands[1..].join(" ")));
+    }
+    if !metadata.description.is_empty() {
+        lines.push(metadata.description.clone());
+    }
+    if !metadata.usage.is_empty() {
+        lines.push(format!("用法：{}", metadata.usage));
+    }
+    if !metadata.examples.is_empty() {
+        lines.push("示例：".to_string());
+        lines.extend(metadata.examples.iter().cloned());
+    }
+    lines.join("\n")
+}
+
+#[async_trait]
+impl MatcherHandler<Message, MessageDeatilTypes> for Help {
+    async fn handle(&self, session: Session<Message, MessageDeatilTypes>) {
+        let metadatas = session
+            .matchers
+            .permitted_metadatas(
+                &Event::from(session.event.clone()),
+                &session.config,
+                &session.caller,
+            )
+            .await;
+        let command = session.event.ty.alt_message.trim();
+        let reply = if command.is_empty() {
+            format_list(&metadatas, &session.config)
+        } else {
+            match metadatas
+                .iter()
+                .find(|metadata| is_command(metadata, command, &session.config))
+            {
+                Some(metadata) => format_detail(metadata, &session.config),
+                None => format!("未找到命令 {}", command),
+            }
+        };
+        let _ = session.send(reply).await;
+    }
+}
+
+/// 列出当前用户可用的命令，`help <命令>` 查看命令用法
+pub fn help() -> Matcher {
+    on_command("help", Help)
+        .boxed()
+        .name("help")
+        .command("help")
+        .description("查看可用命令")
+        .usage("help [命令]")
+        .example("help")
+        .example("help echo")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(name: &str, commands: &[&str]) -> MatcherMetadata {
+        MatcherMetadata {
+            name: Some(name.to_string()),
+            commands: commands.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lookup_by_command() {
+        let config = MatchersConfig {
+            command_start: vec!["/".to_string()],
+            ..Default::default()
+        };
+        let echo = metadata("echo_matcher", &["echo", "复读"]);
+        assert_eq!(command_label(&echo, &config), "/echo");
+        assert!(is_command(&echo, "/echo", &config));
+        assert!(is_command(&echo, "复读", &config));
+        assert!(!is_command(&echo, "echo_matcher", &config));
+        let unnamed = metadata("weather", &[]);
+        assert_eq!(command_label(&unnamed, &config), "weather");
+        assert!(is_command(&unnamed, "weather", &config));
+    }
+}
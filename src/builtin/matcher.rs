@@ -2,16 +2,33 @@ use super::*;
 use crate::{MatcherHandler, PreHandler, Rule};
 use walle_core::event::Message;
 
-pub fn on_command<H, D, S, P, I>(
-    command: &str,
+/// 匹配命令，遵循 `MatchersConfig` 中的命令起始符与分隔符，并去除消息首尾空白
+///
+/// `on_command(command_prefix("echo").alias("复读"), handler)` 声明别名
+pub fn on_command<C, H, D, S, P, I>(
+    command: C,
     handler: H,
 ) -> impl MatcherHandler<Message, D, S, P, I>
 where
+    C: Into<CommandPrefix>,
     H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
 {
-    strip_whitespace()
-        .with(strip_prefix(command))
-        .layer(handler)
+    strip_whitespace().and(command.into()).layer(handler)
+}
+
+/// 匹配命令并按 `A` 的声明解析参数，Handler 可通过 `Args<A>` 提取解析结果
+///
+/// 返回类型须写出 Handler 类型，因此需以 `on_command_args::<MuteArgs, _>("mute", handler)` 调用，
+/// 或使用不需要类型标注的 `MuteArgs::on_command("mute", handler)`
+pub fn on_command_args<A, H>(command: &str, handler: H) -> CommandLayer<H>
+where
+    A: CommandArgs,
+{
+    A::on_command(command, handler)
 }
 
 pub fn on_start_with<H, D, S, P, I>(
@@ -20,20 +37,101 @@ pub fn on_start_with<H, D, S, P, I>(
 ) -> impl MatcherHandler<Message, D, S, P, I>
 where
     H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
 {
     start_with(pat).layer(handler)
 }
 
+pub fn on_end_with<H, D, S, P, I>(pat: &str, handler: H) -> impl MatcherHandler<Message, D, S, P, I>
+where
+    H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    end_with(pat).layer(handler)
+}
+
+pub fn on_full_match<H, D, S, P, I>(
+    pat: &str,
+    handler: H,
+) -> impl MatcherHandler<Message, D, S, P, I>
+where
+    H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    full_match(pat).layer(handler)
+}
+
+/// 消息文本包含任一关键词
+pub fn on_keyword<H, D, S, P, I>(
+    keywords: &[&str],
+    handler: H,
+) -> impl MatcherHandler<Message, D, S, P, I>
+where
+    H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    contains_keyword(keywords).layer(handler)
+}
+
+/// 按消息段模式匹配，Handler 可通过 `ParsedArgs` 提取绑定的参数
+///
+/// `on_pattern("ban {mention} {duration}", handler)`
+pub fn on_pattern<H, D, S, P, I>(pat: &str, handler: H) -> impl MatcherHandler<Message, D, S, P, I>
+where
+    H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    pattern(pat).layer(handler)
+}
+
+/// 正则匹配 `alt_message`，Handler 可通过 `Captures` 提取捕获结果
+pub fn on_regex<H, D, S, P, I>(
+    pattern: &str,
+    handler: H,
+) -> impl MatcherHandler<Message, D, S, P, I>
+where
+    H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    regex_captures(pattern).layer(handler)
+}
+
 pub fn on_mention_me<H, D, S, P, I>(handler: H) -> impl MatcherHandler<Message, D, S, P, I>
 where
     H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
 {
-    strip_whitespace().with(mention_me()).layer(handler)
+    strip_whitespace().and(mention_me()).layer(handler)
 }
 
 pub fn on_to_me<H, D, S, P, I>(handler: H) -> impl MatcherHandler<Message, D, S, P, I>
 where
     H: MatcherHandler<Message, D, S, P, I> + Sync,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
 {
-    strip_whitespace().with(to_me()).layer(handler)
+    strip_whitespace().and(to_me()).layer(handler)
 }
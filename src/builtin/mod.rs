@@ -1,9 +1,25 @@
+mod cancel;
+mod command;
 mod echo;
+mod extract;
+mod help;
 mod matcher;
+mod pattern;
+mod permission;
 mod pre_handle;
+mod regex;
 mod rule;
+mod switch;
 
+pub use self::regex::*;
+pub use cancel::*;
+pub use command::*;
 pub use echo::*;
+pub use extract::*;
+pub use help::*;
 pub use matcher::*;
+pub use pattern::*;
+pub use permission::*;
 pub use pre_handle::*;
 pub use rule::*;
+pub use switch::*;
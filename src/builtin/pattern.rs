@@ -0,0 +1,200 @@
+use super::{tokenize, Arg, ArgKind, ArgValue, ParsedArgs, Token};
+use crate::{PreHandler, Session, Signal};
+use std::collections::HashMap;
+use walle_core::event::Message;
+
+/// 消息段模式中的一项
+#[derive(Debug, Clone)]
+pub enum PatternItem {
+    /// 须完全相同的词
+    Literal(String),
+    /// 绑定到 Session 的参数
+    Bind(Arg),
+}
+
+/// 消息段模式，如 `ban {mention} {duration}`、`setavatar {avatar:image}`
+///
+/// 占位符为 `{类型}` 或 `{名称:类型}`，类型可为 `int`、`duration`、`mention`、`image`、`text`、`rest`，
+/// 未指定名称时以类型为名，重复时依次追加序号，如 `mention`、`mention2`
+#[derive(Debug, Clone)]
+pub struct SegmentPattern {
+    pub items: Vec<PatternItem>,
+}
+
+impl SegmentPattern {
+    /// 解析模式，存在未知类型或 `rest` 不在末尾时 panic
+    pub fn new(pattern: &str) -> Self {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let words: Vec<&str> = pattern.split_whitespace().collect();
+        let items = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let Some(inner) = word.strip_prefix('{').and_then(|w| w.strip_suffix('}')) else {
+                    return PatternItem::Literal(word.to_string());
+                };
+                let (name, kind) = inner.split_once(':').unwrap_or((inner, inner));
+                let kind = match kind {
+                    "int" => ArgKind::Int,
+                    "duration" => ArgKind::Duration,
+                    "mention" => ArgKind::Mention,
+                    "image" => ArgKind::Image,
+                    "text" => ArgKind::Text,
+                    "rest" if i + 1 == words.len() => ArgKind::Rest,
+                    "rest" => panic!("{{rest}} must be the last item of pattern {:?}", pattern),
+                    kind => panic!("unknown kind {:?} in pattern {:?}", kind, pattern),
+                };
+                let count = counts.entry(name.to_string()).or_default();
+                *count += 1;
+                let name = match *count {
+                    1 => name.to_string(),
+                    n => format!("{}{}", name, n),
+                };
+                PatternItem::Bind(Arg::new(&name, kind))
+            })
+            .collect();
+        Self { items }
+    }
+    /// 按模式匹配词元，成功时返回绑定的参数
+    pub fn matches(&self, tokens: &[Token]) -> Option<ParsedArgs> {
+        let mut parsed = HashMap::new();
+        let mut tokens = tokens.iter();
+        for item in &self.items {
+            match item {
+                PatternItem::Literal(literal) => match tokens.next()? {
+                    Token::Word(word) if word == literal => {}
+                    _ => return None,
+                },
+                PatternItem::Bind(arg) if arg.kind == ArgKind::Rest => {
+                    let rest: Vec<String> = tokens.by_ref().map(ToString::to_string).collect();
+                    if rest.is_empty() {
+                        return None;
+                    }
+                    parsed.insert(arg.name.clone(), ArgValue::Text(rest.join(" ")));
+                }
+                PatternItem::Bind(arg) => {
+                    let value = arg.parse_token(tokens.next()?).ok()?;
+                    parsed.insert(arg.name.clone(), value);
+                }
+            }
+        }
+        tokens.next().is_none().then_some(ParsedArgs(parsed))
+    }
+}
+
+/// 匹配成功时将 `ParsedArgs` 与 `Vec<Token>` 存入 Session
+impl<D, S, P, I> PreHandler<Message, D, S, P, I> for SegmentPattern {
+    fn pre_handle(&self, session: &mut Session<Message, D, S, P, I>) -> Signal {
+        let tokens = tokenize(session.message());
+        match self.matches(&tokens) {
+            Some(parsed) => {
+                session.extensions.insert(parsed);
+                session.extensions.insert(tokens);
+                Signal::Matched
+            }
+            None => Signal::NotMatch,
+        }
+    }
+}
+
+/// 消息段模式 PreHandler，Handler 可通过 `ParsedArgs` 提取绑定的参数
+pub fn pattern(pattern: &str) -> SegmentPattern {
+    SegmentPattern::new(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session};
+    use std::time::Duration;
+    use walle_core::{event::Group, segment::MsgSegment, value_map};
+
+    fn segment_session(segments: Vec<MsgSegment>) -> Session<Message, Group> {
+        let mut session = session(message_event("1", "u", Some("g"), ""));
+        *session.message_mut() = segments;
+        session.update_alt();
+        session
+    }
+
+    fn text(text: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "text".to_string(),
+            data: value_map! {"text": text},
+        }
+    }
+
+    fn mention(user_id: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "mention".to_string(),
+            data: value_map! {"user_id": user_id},
+        }
+    }
+
+    fn image(file_id: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "image".to_string(),
+            data: value_map! {"file_id": file_id},
+        }
+    }
+
+    #[test]
+    fn pattern_binds_segments() {
+        let pre = pattern("ban {mention} {duration} {reason:rest}");
+        let mut session = segment_session(vec![text("ban "), mention("v"), text(" 10m 刷屏 太多")]);
+        assert_eq!(pre.pre_handle(&mut session), Signal::Matched);
+        let parsed = session.extensions.get::<ParsedArgs>().unwrap();
+        assert_eq!(parsed.mention("mention"), Some("v"));
+        assert_eq!(parsed.duration("duration"), Some(Duration::from_secs(600)));
+        assert_eq!(parsed.text("reason"), Some("刷屏 太多"));
+
+        let pre = pattern("setavatar {avatar:image}");
+        let mut session = segment_session(vec![text("setavatar"), image("f")]);
+        assert_eq!(pre.pre_handle(&mut session), Signal::Matched);
+        let parsed = session.extensions.get::<ParsedArgs>().unwrap();
+        assert_eq!(
+            parsed.image("avatar").map(|i| i.file_id.as_str()),
+            Some("f")
+        );
+    }
+
+    #[test]
+    fn repeated_kinds_are_numbered() {
+        let pre = pattern("swap {mention} {mention}");
+        let mut session = segment_session(vec![text("swap "), mention("a"), mention("b")]);
+        assert_eq!(pre.pre_handle(&mut session), Signal::Matched);
+        let parsed = session.extensions.get::<ParsedArgs>().unwrap();
+        assert_eq!(parsed.mention("mention"), Some("a"));
+        assert_eq!(parsed.mention("mention2"), Some("b"));
+    }
+
+    #[test]
+    fn pattern_rejects_mismatch() {
+        let pre = pattern("ban {mention} {duration}");
+        for segments in [
+            // 字面量不同
+            vec![text("kick "), mention("v"), text(" 10m")],
+            // 类型不符
+            vec![text("ban v 10m")],
+            vec![text("ban "), mention("v"), text(" soon")],
+            // 缺少或多余的参数
+            vec![text("ban "), mention("v")],
+            vec![text("ban "), mention("v"), text(" 10m extra")],
+        ] {
+            let mut session = segment_session(segments);
+            assert_eq!(pre.pre_handle(&mut session), Signal::NotMatch);
+            assert!(session.extensions.get::<ParsedArgs>().is_none());
+        }
+        // rest 至少需要一个词
+        let pre = pattern("say {rest}");
+        assert_eq!(
+            pre.pre_handle(&mut segment_session(vec![text("say")])),
+            Signal::NotMatch
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown kind")]
+    fn unknown_kind_panics() {
+        pattern("ban {user:member}");
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{AsyncRule, FromSession, Role, Rule, Session, Signal};
+use std::{future::Future, pin::Pin, time::Duration};
+use tracing::warn;
+use walle_core::{
+    action::GetGroupMemberInfo,
+    event::{Group, Message, MessageDeatilTypes, Private},
+    prelude::{async_trait, WalleError},
+    WalleResult,
+};
+
+fn is_superuser<D, S, P, I>(session: &Session<Message, D, S, P, I>) -> bool {
+    session
+        .config
+        .superusers
+        .contains(&session.event.ty.user_id)
+}
+
+/// 超级用户，同时实现 Rule 与 AsyncRule
+pub struct Superuser;
+
+impl<D, S, P, I> Rule<Message, D, S, P, I> for Superuser {
+    fn rule(&self, session: &Session<Message, D, S, P, I>) -> Signal {
+        if is_superuser(session) {
+            Signal::Matched
+        } else {
+            Signal::NotMatch
+        }
+    }
+}
+
+impl<D, S, P, I> AsyncRule<Message, D, S, P, I> for Superuser
+where
+    D: Sync,
+    S: Sync,
+    P: Sync,
+    I: Sync,
+{
+    fn rule<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<Message, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        let signal = Rule::rule(self, session);
+        Box::pin(async move { signal })
+    }
+}
+
+/// 事件发送者为 `MatchersConfig::superusers` 中的超级用户
+pub fn superuser() -> Superuser {
+    Superuser
+}
+
+/// 私聊消息，同时实现 Rule 与 AsyncRule
+pub struct PrivateOnly;
+
+impl<S, P, I> Rule<Message, Private, S, P, I> for PrivateOnly {
+    fn rule(&self, _: &Session<Message, Private, S, P, I>) -> Signal {
+        Signal::Matched
+    }
+}
+
+impl<S, P, I> Rule<Message, Group, S, P, I> for PrivateOnly {
+    fn rule(&self, _: &Session<Message, Group, S, P, I>) -> Signal {
+        Signal::NotMatch
+    }
+}
+
+impl<S, P, I> Rule<Message, MessageDeatilTypes, S, P, I> for PrivateOnly {
+    fn rule(&self, session: &Session<Message, MessageDeatilTypes, S, P, I>) -> Signal {
+        match session.event.detail_type {
+            MessageDeatilTypes::Private(_) => Signal::Matched,
+            MessageDeatilTypes::Group(_) => Signal::NotMatch,
+        }
+    }
+}
+
+impl<D, S, P, I> AsyncRule<Message, D, S, P, I> for PrivateOnly
+where
+    Self: Rule<Message, D, S, P, I>,
+    D: Sync,
+    S: Sync,
+    P: Sync,
+    I: Sync,
+{
+    fn rule<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<Message, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        let signal = Rule::rule(self, session);
+        Box::pin(async move { signal })
+    }
+}
+
+/// 仅限私聊
+pub fn private_only() -> PrivateOnly {
+    PrivateOnly
+}
+
+/// 查询事件发送者在群中的角色，结果按 `MatchersConfig::role_cache_ttl` 缓存
+pub async fn group_role<D, S, P, I>(
+    session: &Session<Message, D, S, P, I>,
+    group_id: &str,
+) -> WalleResult<Role>
+where
+    D: Sync,
+    S: Sync,
+    P: Sync,
+    I: Sync,
+{
+    let user_id = &session.event.ty.user_id;
+    let key = format!(
+        "{}:{}:{}",
+        session.event.ty.selft.user_id, group_id, user_id
+    );
+    let ttl = Duration::from_secs(session.config.role_cache_ttl);
+    if let Some(role) = session.matchers.cached_role(&key, ttl) {
+        return Ok(role);
+    }
+    let action = GetGroupMemberInfo {
+        group_id: group_id.to_string(),
+        user_id: user_id.to_string(),
+    };
+    // 查询失败时同样缓存为普通成员，避免每条消息都重复调用 Action
+    let info = match crate::ActionCaller::call_action(session, action.into())
+        .await
+        .and_then(|resp| resp.as_result().map_err(WalleError::RespError))
+    {
+        Ok(info) => info,
+        Err(e) => {
+            session.matchers.cache_role(key, Role::Member, ttl);
+            return Err(e);
+        }
+    };
+    let implt = session.caller.get_impl(&session.event.ty.selft).await;
+    let mapping = session
+        .config
+        .roles
+        .get(&implt)
+        .cloned()
+        .unwrap_or_default();
+    let role = info
+        .as_map()
+        .and_then(|info| info.get(&mapping.field))
+        .and_then(|role| role.as_str())
+        .map(|name| mapping.role(name))
+        .unwrap_or(Role::Member);
+    session.matchers.cache_role(key, role, ttl);
+    Ok(role)
+}
+
+/// 群成员角色不低于 `role`，超级用户总是通过
+pub struct RoleRule {
+    pub role: Role,
+}
+
+#[async_trait]
+impl<D, S, P, I> AsyncRule<Message, D, S, P, I> for RoleRule
+where
+    Group: FromSession<Message, D, S, P, I>,
+    D: Send + Sync,
+    S: Send + Sync,
+    P: Send + Sync,
+    I: Send + Sync,
+{
+    async fn rule(&self, session: &Session<Message, D, S, P, I>) -> Signal {
+        if is_superuser(session) {
+            return Signal::Matched;
+        }
+        let Some(group) = Group::from_session(session) else {
+            return Signal::NotMatch;
+        };
+        match group_role(session, &group.group_id).await {
+            Ok(role) if role >= self.role => Signal::Matched,
+            Ok(_) => Signal::NotMatch,
+            Err(e) => {
+                warn!(target: "Walle", "get group member role failed: {}", e);
+                Signal::NotMatch
+            }
+        }
+    }
+}
+
+/// 群管理员或群主
+pub fn group_admin() -> RoleRule {
+    RoleRule { role: Role::Admin }
+}
+
+/// 群主
+pub fn group_owner() -> RoleRule {
+    RoleRule { role: Role::Owner }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::on_command;
+    use crate::testing::{message_event, session, DummyCaller};
+    use crate::{handler_fn, MatcherHandlerExt, MatchersConfig, MatchersHandle};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use walle_core::{resp::Resp, value_map};
+
+    /// 以返回 `resp` 的 DummyCaller 构造群消息 Session
+    fn group_session(resp: Resp) -> (Arc<DummyCaller>, Session<Message, Group>) {
+        let (dummy, caller) = DummyCaller::arc();
+        *dummy.resp.lock().unwrap() = Some(resp);
+        let mut session = session(message_event("1", "u", Some("g"), "hi"));
+        session.caller = caller;
+        (dummy, session)
+    }
+
+    #[tokio::test]
+    async fn role_rule_compares_member_role() {
+        let (dummy, session) = group_session(value_map! {"role": "admin"}.into());
+        assert!(AsyncRule::rule(&group_admin(), &session).await.is_matched());
+        assert!(!AsyncRule::rule(&group_owner(), &session).await.is_matched());
+        // 第二次判断命中缓存
+        assert_eq!(dummy.actions.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_lookup_is_cached() {
+        let (dummy, session) = group_session(Resp::failed(35000, (), "not found"));
+        assert!(!AsyncRule::rule(&group_admin(), &session).await.is_matched());
+        assert!(!AsyncRule::rule(&group_admin(), &session).await.is_matched());
+        assert_eq!(dummy.actions.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn async_permission_skips_unmatched_messages() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let handle = MatchersHandle::default();
+        let counter = count.clone();
+        handle
+            .insert(
+                on_command(
+                    "admin",
+                    handler_fn(move |_: Session<Message, Group>| {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        async {}
+                    }),
+                )
+                .with_async_permission(group_admin())
+                .boxed(),
+            )
+            .await;
+        let (dummy, caller) = DummyCaller::arc();
+        *dummy.resp.lock().unwrap() = Some(value_map! {"role": "owner"}.into());
+        let config = Arc::new(MatchersConfig::default());
+        handle
+            .call(
+                &message_event("1", "u", Some("g"), "hello"),
+                &config,
+                &caller,
+            )
+            .await;
+        assert!(dummy.actions.lock().unwrap().is_empty());
+        handle
+            .call(
+                &message_event("2", "u", Some("g"), "admin"),
+                &config,
+                &caller,
+            )
+            .await;
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
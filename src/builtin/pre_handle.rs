@@ -1,4 +1,4 @@
-use crate::{pre_handle_fn, PreHandler, Session, Signal};
+use crate::{normalize_text, pre_handle_fn, MatchersConfig, PreHandler, Session, Signal};
 use walle_core::{
     event::{BaseEvent, Message},
     prelude::MsgSegment,
@@ -13,7 +13,9 @@ fn text_mut(seg: &mut MsgSegment) -> Option<&mut String> {
     }
 }
 
-fn first_text_mut<D, S, P, I>(event: &mut BaseEvent<Message, D, S, P, I>) -> Option<&mut String> {
+pub(super) fn first_text_mut<D, S, P, I>(
+    event: &mut BaseEvent<Message, D, S, P, I>,
+) -> Option<&mut String> {
     event.ty.message.first_mut().and_then(text_mut)
 }
 
@@ -47,58 +49,297 @@ where
     }
 }
 
+/// 命令前缀，依次匹配 `MatchersConfig` 中的命令起始符、命令名或别名及分隔符，并将其去除
+#[derive(Debug, Clone)]
+pub struct CommandPrefix {
+    /// 命令名，其后为别名
+    pub names: Vec<String>,
+}
+
+impl CommandPrefix {
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.names.push(alias.to_string());
+        self
+    }
+    /// 返回去除命令起始符及命令前缀后的文本
+    fn strip<'a>(&self, text: &'a str, config: &MatchersConfig) -> Option<&'a str> {
+        let mut starts: Vec<&String> = config.command_start.iter().collect();
+        starts.sort_by_key(|start| std::cmp::Reverse(start.len()));
+        let text = text.trim_start();
+        starts
+            .iter()
+            .find_map(|start| self.strip_name(text.strip_prefix(start.as_str())?, config))
+    }
+    /// 返回去除命令名或别名及分隔符后的文本
+    ///
+    /// 分隔符为空时命令名后须为空白或文本结尾，避免 `echo` 匹配 `echoes`
+    fn strip_name<'a>(&self, text: &'a str, config: &MatchersConfig) -> Option<&'a str> {
+        let mut names: Vec<&String> = self.names.iter().collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        names.iter().find_map(|name| {
+            let rest = text.strip_prefix(name.as_str())?;
+            if rest.is_empty() {
+                Some(rest)
+            } else if config.command_sep.is_empty() {
+                rest.starts_with(char::is_whitespace).then_some(rest)
+            } else {
+                rest.strip_prefix(config.command_sep.as_str())
+            }
+        })
+    }
+}
+
+impl From<&str> for CommandPrefix {
+    fn from(name: &str) -> Self {
+        command_prefix(name)
+    }
+}
+
+/// 提及 bot 或以昵称开头的消息可省略命令起始符
+impl<D, S, P, I> PreHandler<Message, D, S, P, I> for CommandPrefix {
+    fn pre_handle(&self, session: &mut Session<Message, D, S, P, I>) -> Signal {
+        let config = session.config.clone();
+        if let Some(text) = first_text_mut(&mut session.event) {
+            if let Some(s) = self.strip(text, &config) {
+                *text = s.to_string();
+                session.update_alt();
+                return Signal::Matched;
+            }
+        }
+        let message = session.event.ty.message.clone();
+        if _to_me(session) == Signal::Matched {
+            if let Some(text) = first_text_mut(&mut session.event) {
+                if let Some(s) = self
+                    .strip(text, &config)
+                    .or_else(|| self.strip_name(text.trim_start(), &config))
+                {
+                    *text = s.to_string();
+                    session.update_alt();
+                    return Signal::Matched;
+                }
+            }
+            session.event.ty.message = message;
+            session.update_alt();
+        }
+        Signal::NotMatch
+    }
+}
+
+/// 命令前缀，可通过 `alias` 添加别名
+pub fn command_prefix(name: &str) -> CommandPrefix {
+    CommandPrefix {
+        names: vec![name.to_string()],
+    }
+}
+
+/// 规范化消息中的文本段，见 `normalize_text`，总是返回 `Signal::Matched`
+pub fn normalize<D, S, P, I>() -> impl PreHandler<Message, D, S, P, I> {
+    pre_handle_fn(|session| {
+        for seg in session.message_mut().iter_mut() {
+            if let Some(text) = text_mut(seg) {
+                *text = normalize_text(text);
+            }
+        }
+        session.update_alt();
+        Signal::Matched
+    })
+}
+
+/// 去除消息首尾空白（包括全角空格），总是返回 `Signal::Matched`
 pub fn strip_whitespace<D, S, P, I>() -> impl PreHandler<Message, D, S, P, I> {
     pre_handle_fn(|session| {
-        let mut sig = Signal::NotMatch;
+        let mut stripped = false;
         if let Some(text) = first_text_mut(&mut session.event) {
-            while let Some(s) = text.strip_prefix(' ') {
-                *text = s.to_string();
-                sig = Signal::Matched;
+            let trimmed = text.trim_start();
+            if trimmed.len() != text.len() {
+                *text = trimmed.to_string();
+                stripped = true;
             }
         }
         if let Some(text) = last_text_mut(&mut session.event) {
-            while let Some(s) = text.strip_suffix(' ') {
-                *text = s.to_string();
-                sig = Signal::Matched;
+            let trimmed = text.trim_end();
+            if trimmed.len() != text.len() {
+                *text = trimmed.to_string();
+                stripped = true;
             }
         }
-        if sig != Signal::NotMatch {
+        if stripped {
             session.update_alt();
         }
-        sig
+        Signal::Matched
     })
 }
 
+/// 匹配提及 bot 的消息，并去除该提及消息段
 fn _mention_me<D, S, P, I>(session: &mut Session<Message, D, S, P, I>) -> Signal {
     let segments = &mut session.event.ty.message;
     let self_id = Value::Str(session.event.ty.selft.user_id.clone());
-    for i in 0..segments.len() {
-        let seg = segments.get(i).unwrap();
-        if seg.ty.as_str() == "mention" {
-            if seg.data.get("user_id") == Some(&self_id) {
-                session.update_alt();
-                return Signal::Matched;
-            }
+    let Some(i) = segments
+        .iter()
+        .position(|seg| seg.ty.as_str() == "mention" && seg.data.get("user_id") == Some(&self_id))
+    else {
+        return Signal::NotMatch;
+    };
+    segments.remove(i);
+    if i == 0 {
+        if let Some(text) = first_text_mut(&mut session.event) {
+            *text = text.trim_start().to_string();
         }
     }
-    Signal::NotMatch
+    session.update_alt();
+    Signal::Matched
 }
 
 pub fn mention_me<D, S, P, I>() -> impl PreHandler<Message, D, S, P, I> {
     pre_handle_fn(_mention_me)
 }
 
-pub fn to_me<D, S, P, I>() -> impl PreHandler<Message, D, S, P, I> {
-    pre_handle_fn(|session| {
-        if let Some(text) = first_text_mut(&mut session.event) {
-            for nickname in &session.config.nicknames {
-                if let Some(s) = text.strip_prefix(nickname) {
-                    *text = s.to_string();
-                    session.update_alt();
-                    return Signal::Matched;
-                }
+/// 去除开头的昵称，昵称以字母或数字结尾时其后须不是字母或数字，避免 `bot` 匹配 `botany`
+fn strip_nickname<'a>(text: &'a str, nickname: &str) -> Option<&'a str> {
+    let rest = text.strip_prefix(nickname)?;
+    let joined = nickname
+        .chars()
+        .last()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && rest.starts_with(|c: char| c.is_ascii_alphanumeric());
+    (!joined).then_some(rest)
+}
+
+/// 匹配以昵称开头或提及 bot 的消息，并去除昵称或该提及消息段
+fn _to_me<D, S, P, I>(session: &mut Session<Message, D, S, P, I>) -> Signal {
+    if let Some(text) = first_text_mut(&mut session.event) {
+        for nickname in &session.config.nicknames {
+            if let Some(s) = strip_nickname(text, nickname) {
+                *text = s.to_string();
+                session.update_alt();
+                return Signal::Matched;
             }
         }
-        _mention_me(session)
-    })
+    }
+    _mention_me(session)
+}
+
+pub fn to_me<D, S, P, I>() -> impl PreHandler<Message, D, S, P, I> {
+    pre_handle_fn(_to_me)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session};
+    use std::sync::Arc;
+    use walle_core::{event::Group, value_map};
+
+    fn command_session(text: &str, config: MatchersConfig) -> Session<Message, Group> {
+        let mut session = session(message_event("1", "u", Some("g"), text));
+        session.config = Arc::new(config);
+        session
+    }
+
+    fn slash_config() -> MatchersConfig {
+        MatchersConfig {
+            command_start: vec!["/".to_string()],
+            nicknames: vec!["bot".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn command_requires_word_boundary() {
+        let prefix = command_prefix("echo");
+        let config = MatchersConfig::default();
+        assert_eq!(prefix.strip("echo hi", &config), Some(" hi"));
+        assert_eq!(prefix.strip("echo", &config), Some(""));
+        for text in ["echoes", "echo123", "helpecho"] {
+            assert_eq!(prefix.strip(text, &config), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn command_with_separator() {
+        let prefix = command_prefix("echo").alias("复读");
+        let config = MatchersConfig {
+            command_sep: ".".to_string(),
+            ..slash_config()
+        };
+        assert_eq!(prefix.strip("/echo.hi", &config), Some("hi"));
+        assert_eq!(prefix.strip("/复读", &config), Some(""));
+        assert_eq!(prefix.strip("/echo hi", &config), None);
+        assert_eq!(prefix.strip("echo.hi", &config), None);
+    }
+
+    #[test]
+    fn to_me_command_skips_start() {
+        let mut session = command_session("bot echo hi", slash_config());
+        assert_eq!(
+            command_prefix("echo").pre_handle(&mut session),
+            Signal::Matched
+        );
+        assert_eq!(session.event.ty.alt_message, " hi");
+
+        let mut session = command_session(" echo hi", slash_config());
+        session.event.ty.message.insert(
+            0,
+            MsgSegment {
+                ty: "mention".to_string(),
+                data: value_map! {"user_id": "bot"},
+            },
+        );
+        assert_eq!(
+            command_prefix("echo").pre_handle(&mut session),
+            Signal::Matched
+        );
+        assert_eq!(session.event.ty.alt_message, " hi");
+
+        let mut session = command_session("bot /echo hi", slash_config());
+        assert_eq!(
+            command_prefix("echo").pre_handle(&mut session),
+            Signal::Matched
+        );
+        assert_eq!(session.event.ty.alt_message, " hi");
+
+        let mut session = command_session(" /echo hi", slash_config());
+        session.event.ty.message.insert(
+            0,
+            MsgSegment {
+                ty: "mention".to_string(),
+                data: value_map! {"user_id": "bot"},
+            },
+        );
+        assert_eq!(
+            command_prefix("echo").pre_handle(&mut session),
+            Signal::Matched
+        );
+        assert_eq!(session.event.ty.alt_message, " hi");
+
+        for text in ["echo hi", "botany echo hi"] {
+            let mut session = command_session(text, slash_config());
+            assert_eq!(
+                command_prefix("echo").pre_handle(&mut session),
+                Signal::NotMatch,
+                "{}",
+                text
+            );
+            assert_eq!(session.event.ty.alt_message, text);
+        }
+    }
+
+    #[test]
+    fn nickname_requires_word_boundary() {
+        assert_eq!(strip_nickname("bot echo", "bot"), Some(" echo"));
+        assert_eq!(strip_nickname("bot,echo", "bot"), Some(",echo"));
+        assert_eq!(strip_nickname("bot", "bot"), Some(""));
+        assert_eq!(strip_nickname("botany", "bot"), None);
+        assert_eq!(strip_nickname("小白echo", "小白"), Some("echo"));
+    }
+
+    #[test]
+    fn unmatched_to_me_is_restored() {
+        let mut session = command_session("bot help", slash_config());
+        assert_eq!(
+            command_prefix("echo").pre_handle(&mut session),
+            Signal::NotMatch
+        );
+        assert_eq!(session.event.ty.alt_message, "bot help");
+    }
 }
@@ -0,0 +1,211 @@
+use super::pre_handle::first_text_mut;
+use crate::{FromSession, PreHandler, Rule, Session, Signal};
+use ::regex::Regex;
+use std::{collections::HashMap, str::FromStr};
+use walle_core::event::Message;
+
+/// 正则匹配的目标文本
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegexTarget {
+    /// `alt_message`
+    AltMessage,
+    /// 第一个消息段（须为文本段）
+    FirstText,
+}
+
+fn target_text<D, S, P, I>(
+    session: &Session<Message, D, S, P, I>,
+    target: RegexTarget,
+) -> Option<&str> {
+    match target {
+        RegexTarget::AltMessage => Some(&session.event.ty.alt_message),
+        RegexTarget::FirstText => session.event.ty.message.first().and_then(|seg| {
+            if seg.ty == "text" {
+                seg.data.get("text").and_then(|v| v.as_str())
+            } else {
+                None
+            }
+        }),
+    }
+}
+
+/// 正则捕获组，由 `RegexCaptures` 存入 Session
+#[derive(Debug, Clone, Default)]
+pub struct Captures {
+    /// 位置捕获组，0 为整个匹配
+    pub groups: Vec<Option<String>>,
+    /// 命名捕获组
+    pub named: HashMap<String, String>,
+}
+
+impl Captures {
+    fn new(regex: &Regex, caps: &::regex::Captures) -> Self {
+        Self {
+            groups: caps
+                .iter()
+                .map(|m| m.map(|m| m.as_str().to_string()))
+                .collect(),
+            named: regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| Some((name.to_string(), caps.name(name)?.as_str().to_string())))
+                .collect(),
+        }
+    }
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.groups.get(index)?.as_deref()
+    }
+    pub fn name(&self, name: &str) -> Option<&str> {
+        self.named.get(name).map(String::as_str)
+    }
+    /// 将位置捕获组解析为 `T`
+    pub fn parse<T: FromStr>(&self, index: usize) -> Option<T> {
+        self.get(index)?.parse().ok()
+    }
+    /// 将命名捕获组解析为 `T`
+    pub fn parse_name<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.name(name)?.parse().ok()
+    }
+}
+
+impl<D, S, P, I> FromSession<Message, D, S, P, I> for Captures {
+    fn from_session(session: &Session<Message, D, S, P, I>) -> Option<Self> {
+        session.extensions.get::<Self>().cloned()
+    }
+}
+
+/// 正则 Rule
+pub struct RegexRule {
+    pub regex: Regex,
+    pub target: RegexTarget,
+}
+
+impl RegexRule {
+    /// 匹配第一个文本段而非 `alt_message`
+    pub fn first_text(mut self) -> Self {
+        self.target = RegexTarget::FirstText;
+        self
+    }
+}
+
+impl<D, S, P, I> Rule<Message, D, S, P, I> for RegexRule {
+    fn rule(&self, session: &Session<Message, D, S, P, I>) -> Signal {
+        match target_text(session, self.target) {
+            Some(text) if self.regex.is_match(text) => Signal::Matched,
+            _ => Signal::NotMatch,
+        }
+    }
+}
+
+/// 正则匹配 `alt_message`，pattern 非法时 panic
+pub fn regex(pattern: &str) -> RegexRule {
+    RegexRule {
+        regex: Regex::new(pattern).expect("invalid regex pattern"),
+        target: RegexTarget::AltMessage,
+    }
+}
+
+/// 正则 PreHandler，匹配成功时将 `Captures` 存入 Session
+pub struct RegexCaptures {
+    pub regex: Regex,
+    pub target: RegexTarget,
+    /// 匹配位于开头时，从第一个文本段去除匹配内容
+    pub strip: bool,
+}
+
+impl RegexCaptures {
+    /// 匹配第一个文本段而非 `alt_message`
+    pub fn first_text(mut self) -> Self {
+        self.target = RegexTarget::FirstText;
+        self
+    }
+    /// 去除位于开头的匹配内容，与 `StripPrefix` 相同
+    pub fn strip(mut self) -> Self {
+        self.strip = true;
+        self
+    }
+}
+
+impl<D, S, P, I> PreHandler<Message, D, S, P, I> for RegexCaptures {
+    fn pre_handle(&self, session: &mut Session<Message, D, S, P, I>) -> Signal {
+        let Some(text) = target_text(session, self.target) else {
+            return Signal::NotMatch;
+        };
+        let Some(caps) = self.regex.captures(text) else {
+            return Signal::NotMatch;
+        };
+        let anchored = caps.get(0).is_some_and(|m| m.start() == 0);
+        let captures = Captures::new(&self.regex, &caps);
+        if self.strip && anchored {
+            let matched = captures.get(0).unwrap_or_default();
+            if let Some(text) = first_text_mut(&mut session.event) {
+                if let Some(s) = text.strip_prefix(matched) {
+                    *text = s.to_string();
+                    session.update_alt();
+                }
+            }
+        }
+        session.extensions.insert(captures);
+        Signal::Matched
+    }
+}
+
+/// 正则匹配 `alt_message` 并捕获分组，pattern 非法时 panic
+///
+/// Handler 可通过 `Captures` 提取捕获结果
+pub fn regex_captures(pattern: &str) -> RegexCaptures {
+    RegexCaptures {
+        regex: Regex::new(pattern).expect("invalid regex pattern"),
+        target: RegexTarget::AltMessage,
+        strip: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session};
+    use walle_core::event::Private;
+
+    fn text_session(text: &str) -> Session<Message, Private> {
+        session(message_event("1", "u", None, text))
+    }
+
+    #[test]
+    fn regex_rule_matches() {
+        let rule = regex(r"^roll \d+$");
+        assert_eq!(rule.rule(&text_session("roll 20")), Signal::Matched);
+        assert_eq!(rule.rule(&text_session("roll dice")), Signal::NotMatch);
+    }
+
+    #[test]
+    fn captures_are_stored() {
+        let pre = regex_captures(r"(?P<count>\d+)d(\d+)");
+        let mut session = text_session("roll 2d6 please");
+        assert_eq!(pre.pre_handle(&mut session), Signal::Matched);
+        let captures = Captures::from_session(&session).unwrap();
+        assert_eq!(captures.get(0), Some("2d6"));
+        assert_eq!(captures.parse::<u32>(2), Some(6));
+        assert_eq!(captures.name("count"), Some("2"));
+        assert_eq!(captures.parse_name::<u32>("count"), Some(2));
+        assert_eq!(captures.name("sides"), None);
+        // 未锚定的匹配不会被去除
+        assert_eq!(session.event.ty.alt_message, "roll 2d6 please");
+
+        let mut session = text_session("roll");
+        assert_eq!(pre.pre_handle(&mut session), Signal::NotMatch);
+        assert!(session.extensions.get::<Captures>().is_none());
+    }
+
+    #[test]
+    fn anchored_match_is_stripped() {
+        let pre = regex_captures(r"^(\d+)d").first_text().strip();
+        let mut session = text_session("3d 骰子");
+        assert_eq!(pre.pre_handle(&mut session), Signal::Matched);
+        assert_eq!(session.event.ty.alt_message, " 骰子");
+        assert_eq!(
+            session.extensions.get::<Captures>().unwrap().get(1),
+            Some("3")
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::Signal;
-use crate::{rule_fn, Rule, Session};
+use crate::{rule_fn, Channel, Rule, Session};
 use walle_core::event::{Group, Message, MessageDeatilTypes};
 
 pub struct UserIdChecker {
@@ -59,15 +59,153 @@ where
     }
 }
 
-pub fn start_with<D, S, P, I>(pat: &str) -> impl Rule<Message, D, S, P, I> {
-    let word = pat.to_string();
-    rule_fn(move |session: &Session<Message, D, S, P, I>| -> Signal {
-        if session.event.ty.alt_message.starts_with(&word) {
+pub struct ChannelIdChecker {
+    pub guild_id: String,
+    pub channel_id: String,
+}
+
+impl<S, P, I> Rule<Message, Channel, S, P, I> for ChannelIdChecker {
+    fn rule(&self, session: &Session<Message, Channel, S, P, I>) -> Signal {
+        let channel = &session.event.detail_type;
+        if channel.guild_id == self.guild_id && channel.channel_id == self.channel_id {
+            Signal::Matched
+        } else {
+            Signal::NotMatch
+        }
+    }
+}
+
+pub fn channel_id_check<S>(guild_id: S, channel_id: S) -> ChannelIdChecker
+where
+    S: ToString,
+{
+    ChannelIdChecker {
+        guild_id: guild_id.to_string(),
+        channel_id: channel_id.to_string(),
+    }
+}
+
+/// `alt_message` 以 `pat` 开头
+#[derive(Debug, Clone)]
+pub struct StartWith {
+    pub pat: String,
+    pub ignore_case: bool,
+}
+
+impl StartWith {
+    /// 忽略大小写
+    pub fn case_insensitive(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+}
+
+impl<D, S, P, I> Rule<Message, D, S, P, I> for StartWith {
+    fn rule(&self, session: &Session<Message, D, S, P, I>) -> Signal {
+        let alt = &session.event.ty.alt_message;
+        let matched = if self.ignore_case {
+            alt.to_lowercase().starts_with(&self.pat.to_lowercase())
+        } else {
+            alt.starts_with(&self.pat)
+        };
+        if matched {
             Signal::Matched
         } else {
             Signal::NotMatch
         }
-    })
+    }
+}
+
+pub fn start_with(pat: &str) -> StartWith {
+    StartWith {
+        pat: pat.to_string(),
+        ignore_case: false,
+    }
+}
+
+/// 消息中全部文本段拼接后的内容，图片、提及等消息段被跳过
+pub(crate) fn plain_text<D, S, P, I>(session: &Session<Message, D, S, P, I>) -> String {
+    session
+        .event
+        .ty
+        .message
+        .iter()
+        .filter(|seg| seg.ty == "text")
+        .filter_map(|seg| seg.data.get("text").and_then(|v| v.as_str()))
+        .collect()
+}
+
+/// 文本匹配方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextPattern {
+    EndWith(String),
+    FullMatch(String),
+    /// 包含任一关键词
+    Keywords(Vec<String>),
+}
+
+/// 对消息文本段进行匹配的 Rule
+#[derive(Debug, Clone)]
+pub struct TextRule {
+    pub pattern: TextPattern,
+    pub ignore_case: bool,
+}
+
+impl TextRule {
+    /// 忽略大小写
+    pub fn case_insensitive(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+    fn is_match(&self, text: &str) -> bool {
+        let fold = |s: &str| {
+            if self.ignore_case {
+                s.to_lowercase()
+            } else {
+                s.to_string()
+            }
+        };
+        let text = fold(text.trim());
+        match &self.pattern {
+            TextPattern::EndWith(pat) => text.ends_with(&fold(pat)),
+            TextPattern::FullMatch(pat) => text == fold(pat),
+            TextPattern::Keywords(keywords) => keywords.iter().any(|k| text.contains(&fold(k))),
+        }
+    }
+}
+
+impl<D, S, P, I> Rule<Message, D, S, P, I> for TextRule {
+    fn rule(&self, session: &Session<Message, D, S, P, I>) -> Signal {
+        if self.is_match(&plain_text(session)) {
+            Signal::Matched
+        } else {
+            Signal::NotMatch
+        }
+    }
+}
+
+fn text_rule(pattern: TextPattern) -> TextRule {
+    TextRule {
+        pattern,
+        ignore_case: false,
+    }
+}
+
+/// 消息文本以 `pat` 结尾
+pub fn end_with(pat: &str) -> TextRule {
+    text_rule(TextPattern::EndWith(pat.to_string()))
+}
+
+/// 消息文本（去除首尾空白）与 `pat` 完全相同
+pub fn full_match(pat: &str) -> TextRule {
+    text_rule(TextPattern::FullMatch(pat.to_string()))
+}
+
+/// 消息文本包含任一关键词
+pub fn contains_keyword(keywords: &[&str]) -> TextRule {
+    text_rule(TextPattern::Keywords(
+        keywords.iter().map(ToString::to_string).collect(),
+    ))
 }
 
 fn _mention_me<D, S, P, I>(session: &Session<Message, D, S, P, I>) -> Signal {
@@ -103,3 +241,75 @@ pub fn mention_me_rule<D, S, P, I>() -> impl Rule<Message, D, S, P, I> {
 pub fn allways_matched<T, D, S, P, I>() -> impl Rule<T, D, S, P, I> {
     rule_fn(|_session| Signal::Matched)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session};
+    use walle_core::{event::Private, segment::MsgSegment, value_map};
+
+    fn text_session(segments: Vec<MsgSegment>) -> Session<Message, Private> {
+        let mut session = session(message_event("1", "u", None, ""));
+        *session.message_mut() = segments;
+        session.update_alt();
+        session
+    }
+
+    fn text(text: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "text".to_string(),
+            data: value_map! {"text": text},
+        }
+    }
+
+    fn mention(user_id: &str) -> MsgSegment {
+        MsgSegment {
+            ty: "mention".to_string(),
+            data: value_map! {"user_id": user_id},
+        }
+    }
+
+    fn matches<R: Rule<Message, Private>>(rule: &R, segments: Vec<MsgSegment>) -> bool {
+        rule.rule(&text_session(segments)) == Signal::Matched
+    }
+
+    #[test]
+    fn start_with_ignores_case() {
+        let rule = start_with("Hi");
+        assert!(matches(&rule, vec![text("Hi there")]));
+        assert!(!matches(&rule, vec![text("hi there")]));
+        let rule = rule.case_insensitive();
+        assert!(matches(&rule, vec![text("HI there")]));
+        assert!(!matches(&rule, vec![text("oh hi")]));
+    }
+
+    #[test]
+    fn end_with_skips_other_segments() {
+        let rule = end_with("吗");
+        assert!(matches(&rule, vec![text("在吗"), mention("bot")]));
+        assert!(!matches(&rule, vec![text("在吗？")]));
+        let rule = end_with("OK").case_insensitive();
+        assert!(matches(&rule, vec![text("that is ok ")]));
+        assert!(!matches(&rule, vec![text("ok then")]));
+    }
+
+    #[test]
+    fn full_match_trims_and_ignores_case() {
+        let rule = full_match("Ping");
+        assert!(matches(&rule, vec![text(" Ping ")]));
+        assert!(!matches(&rule, vec![text("ping")]));
+        let rule = rule.case_insensitive();
+        assert!(matches(&rule, vec![mention("bot"), text(" PING")]));
+        assert!(!matches(&rule, vec![text("ping pong")]));
+    }
+
+    #[test]
+    fn keywords_match_any() {
+        let rule = contains_keyword(&["早安", "Morning"]);
+        assert!(matches(&rule, vec![text("大家早安")]));
+        assert!(!matches(&rule, vec![text("good morning")]));
+        let rule = rule.case_insensitive();
+        assert!(matches(&rule, vec![text("Good MORNING all")]));
+        assert!(!matches(&rule, vec![text("晚安")]));
+    }
+}
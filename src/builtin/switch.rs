@@ -0,0 +1,86 @@
+use super::{on_command, superuser};
+use crate::{Matcher, MatcherHandler, MatcherHandlerExt, ReplyAbleSession, Session, SwitchScope};
+use async_trait::async_trait;
+use walle_core::event::{Message, MessageDeatilTypes};
+
+const USAGE: &str = "<Matcher> [group <群号> | user <用户ID>]";
+
+/// 启用或禁用 Matcher 的管理命令
+pub struct Switch {
+    pub enable: bool,
+}
+
+impl Switch {
+    fn command(&self) -> &'static str {
+        if self.enable {
+            "enable"
+        } else {
+            "disable"
+        }
+    }
+    async fn switch(&self, session: &Session<Message, MessageDeatilTypes>) -> String {
+        let args: Vec<&str> = session.event.ty.alt_message.split_whitespace().collect();
+        let (name, scope) = match args.as_slice() {
+            [name] => match &session.event.detail_type {
+                MessageDeatilTypes::Group(group) => {
+                    (*name, SwitchScope::Group(group.group_id.clone()))
+                }
+                MessageDeatilTypes::Private(_) => {
+                    (*name, SwitchScope::User(session.event.ty.user_id.clone()))
+                }
+            },
+            [name, "group", group_id] => (*name, SwitchScope::Group(group_id.to_string())),
+            [name, "user", user_id] => (*name, SwitchScope::User(user_id.to_string())),
+            _ => return format!("用法：{} {}", self.command(), USAGE),
+        };
+        if name == "enable" || name == "disable" {
+            return format!("无法切换 {}", name);
+        }
+        if !session.matchers.contains(name).await {
+            return format!("未找到 Matcher {}", name);
+        }
+        let result = if self.enable {
+            session.matchers.enable(name, &scope).await
+        } else {
+            session.matchers.disable(name, &scope).await
+        };
+        match (result, self.enable) {
+            (Ok(true), true) => format!("已在{}中启用 {}", scope, name),
+            (Ok(true), false) => format!("已在{}中禁用 {}", scope, name),
+            (Ok(false), true) => format!("{} 在{}中未被禁用", name, scope),
+            (Ok(false), false) => format!("{} 在{}中已被禁用", name, scope),
+            (Err(e), _) => format!("保存开关状态失败：{}", e),
+        }
+    }
+}
+
+#[async_trait]
+impl MatcherHandler<Message, MessageDeatilTypes> for Switch {
+    async fn handle(&self, session: Session<Message, MessageDeatilTypes>) {
+        let reply = self.switch(&session).await;
+        let _ = session.send(reply).await;
+    }
+}
+
+fn switch(enable: bool) -> Matcher {
+    let handler = Switch { enable };
+    let command = handler.command();
+    on_command(command, handler)
+        .with_permission(superuser())
+        .boxed()
+        .name(command)
+        .command(command)
+        .usage(&format!("{} {}", command, USAGE))
+        .example(&format!("{} echo", command))
+        .example(&format!("{} echo group 123456", command))
+}
+
+/// 在当前群或私聊中启用 Matcher，仅超级用户可用
+pub fn enable() -> Matcher {
+    switch(true).description("启用 Matcher")
+}
+
+/// 在当前群或私聊中禁用 Matcher，仅超级用户可用
+pub fn disable() -> Matcher {
+    switch(false).description("禁用 Matcher")
+}
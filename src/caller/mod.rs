@@ -135,7 +135,7 @@ where
 
 macro_rules! action_ext {
     ($fname: ident, $aty: expr => $rty: ty) => {
-        fn $fname<'a, 't>(&'a self) -> Pin<Box<dyn Future<Output = WalleResult<Vec<Event>>> + 't>>
+        fn $fname<'a, 't>(&'a self) -> Pin<Box<dyn Future<Output = WalleResult<$rty>> + Send + 't>>
         where
             'a: 't,
             Self: 't,
@@ -148,7 +148,7 @@ macro_rules! action_ext {
         }
     };
     ($fname: ident, $a: expr => $rty: ty, $($f: ident: $fty: ty),*) => {
-        fn $fname<'a, 't>(&'a self, $($f: $fty),*) -> Pin<Box<dyn Future<Output = WalleResult<Vec<Event>>> + 't>>
+        fn $fname<'a, 't>(&'a self, $($f: $fty),*) -> Pin<Box<dyn Future<Output = WalleResult<$rty>> + Send + 't>>
         where
             'a: 't,
             Self: 't,
@@ -175,7 +175,7 @@ pub trait ActionCallerExt: ActionCaller {
         &'a self,
         limit: i64,
         timeout: i64,
-    ) -> Pin<Box<dyn Future<Output = WalleResult<Vec<Event>>> + 't>>
+    ) -> Pin<Box<dyn Future<Output = WalleResult<Vec<Event>>> + Send + 't>>
     where
         'a: 't,
         Self: 't,
@@ -193,7 +193,7 @@ pub trait ActionCallerExt: ActionCaller {
         guild_id: Option<String>,
         channel_id: Option<String>,
         message: M,
-    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + 't>>
+    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + Send + 't>>
     where
         'a: 't,
         Self: 't,
@@ -212,7 +212,7 @@ pub trait ActionCallerExt: ActionCaller {
         &'a self,
         user_id: String,
         message: M,
-    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + 't>>
+    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + Send + 't>>
     where
         'a: 't,
         Self: 't,
@@ -231,7 +231,7 @@ pub trait ActionCallerExt: ActionCaller {
         &'a self,
         group_id: String,
         message: M,
-    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + 't>>
+    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + Send + 't>>
     where
         'a: 't,
         Self: 't,
@@ -251,7 +251,7 @@ pub trait ActionCallerExt: ActionCaller {
         guild_id: String,
         channel_id: String,
         message: M,
-    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + 't>>
+    ) -> Pin<Box<dyn Future<Output = WalleResult<walle_core::structs::SendMessageResp>> + Send + 't>>
     where
         'a: 't,
         Self: 't,
@@ -1,9 +1,138 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 pub use walle_core::config::*;
 
 /// Matchers 可配置项
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MatchersConfig {
     #[serde(default = "Vec::default")]
     pub nicknames: Vec<String>,
+    /// 超级用户 user_id
+    #[serde(default = "Vec::default")]
+    pub superusers: Vec<String>,
+    /// Handler 出错时回复给用户的消息，为空时不回复
+    #[serde(default = "default_error_message")]
+    pub error_message: String,
+    /// 命令起始符，如 `/`、`!`，空字符串表示无需起始符
+    #[serde(default = "default_command_start")]
+    pub command_start: Vec<String>,
+    /// 命令与参数间的分隔符，为空时不要求分隔
+    #[serde(default = "String::default")]
+    pub command_sep: String,
+    /// 消息以非空命令起始符开头且没有 Matcher 匹配时，回复最接近的命令
+    #[serde(default)]
+    pub command_suggestion: bool,
+    /// 同一会话中两次命令提示的最小间隔，单位秒
+    #[serde(default = "default_suggestion_interval")]
+    pub suggestion_interval: u64,
+    /// 在所有 Matcher 执行前规范化消息文本，见 `normalize_text`
+    #[serde(default)]
+    pub normalize: bool,
+    /// 各实现的群角色映射，键为实现名，缺省时使用 `RoleMapping::default`
+    #[serde(default = "HashMap::default")]
+    pub roles: HashMap<String, RoleMapping>,
+    /// 群成员角色缓存时间，单位秒
+    #[serde(default = "default_role_cache_ttl")]
+    pub role_cache_ttl: u64,
+    /// 多轮对话中用于中止对话的关键词
+    #[serde(default = "default_cancel_keywords")]
+    pub cancel_keywords: Vec<String>,
+    /// Matcher 开关持久化文件，默认为空，即不持久化
+    #[serde(default = "String::default")]
+    pub switch_file: String,
+}
+
+/// 群成员角色
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Member,
+    Admin,
+    Owner,
+}
+
+/// 将 `get_group_member_info` 返回的平台角色名映射为 `Role`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoleMapping {
+    /// 角色所在字段
+    #[serde(default = "default_role_field")]
+    pub field: String,
+    #[serde(default = "default_owner_roles")]
+    pub owner: Vec<String>,
+    #[serde(default = "default_admin_roles")]
+    pub admin: Vec<String>,
+}
+
+impl RoleMapping {
+    /// 未知角色名视为普通成员
+    pub fn role(&self, name: &str) -> Role {
+        if self.owner.iter().any(|owner| owner == name) {
+            Role::Owner
+        } else if self.admin.iter().any(|admin| admin == name) {
+            Role::Admin
+        } else {
+            Role::Member
+        }
+    }
+}
+
+fn default_role_field() -> String {
+    "role".to_string()
+}
+
+fn default_owner_roles() -> Vec<String> {
+    vec!["owner".to_string()]
+}
+
+fn default_admin_roles() -> Vec<String> {
+    vec!["admin".to_string(), "administrator".to_string()]
+}
+
+impl Default for RoleMapping {
+    fn default() -> Self {
+        Self {
+            field: default_role_field(),
+            owner: default_owner_roles(),
+            admin: default_admin_roles(),
+        }
+    }
+}
+
+fn default_role_cache_ttl() -> u64 {
+    300
+}
+
+fn default_cancel_keywords() -> Vec<String> {
+    vec!["取消".to_string()]
+}
+
+fn default_error_message() -> String {
+    "出错了，请稍后再试".to_string()
+}
+
+fn default_command_start() -> Vec<String> {
+    vec![String::default()]
+}
+
+fn default_suggestion_interval() -> u64 {
+    10
+}
+
+impl Default for MatchersConfig {
+    fn default() -> Self {
+        Self {
+            nicknames: vec![],
+            superusers: vec![],
+            error_message: default_error_message(),
+            command_start: default_command_start(),
+            command_sep: String::default(),
+            command_suggestion: false,
+            suggestion_interval: default_suggestion_interval(),
+            normalize: false,
+            roles: HashMap::default(),
+            role_cache_ttl: default_role_cache_ttl(),
+            cancel_keywords: default_cancel_keywords(),
+            switch_file: String::default(),
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+extern crate self as walle;
+
 use walle_core::{action::Action, obc::AppOBC, resp::Resp, OneBot};
 
 pub mod matcher;
@@ -17,6 +19,7 @@ pub use caller::{ActionCaller, ActionCallerExt};
 pub use config::*;
 pub use matcher::*;
 pub use walle_core;
+pub use walle_macros::matcher;
 // #[cfg(feature = "scheduler")]
 // pub use scheduler::*;
 
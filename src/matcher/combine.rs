@@ -0,0 +1,503 @@
+use crate::{AsyncPreHandler, AsyncRule, PreHandler, Rule, Session, Signal};
+use async_trait::async_trait;
+
+/// 逻辑与，左侧不匹配时不再执行右侧
+pub struct And<A, B>(pub A, pub B);
+
+/// 逻辑或，左侧匹配时不再执行右侧
+///
+/// 作为 PreHandler 使用时不会还原左侧在不匹配前对 Session 的修改，右侧将看到修改后的 Session
+pub struct Or<A, B>(pub A, pub B);
+
+/// 逻辑非
+///
+/// 作为 PreHandler 使用时，内部 PreHandler 在不匹配前对 Session 的修改会被保留
+pub struct Not<A>(pub A);
+
+/// 异步逻辑与，左侧不匹配时不再执行右侧
+pub struct AsyncAnd<A, B>(pub A, pub B);
+
+/// 异步逻辑或，左侧匹配时不再执行右侧
+pub struct AsyncOr<A, B>(pub A, pub B);
+
+/// 异步逻辑非
+pub struct AsyncNot<A>(pub A);
+
+/// 全部匹配时匹配，按顺序执行并在首个不匹配处短路
+pub struct AllOf<A>(pub A);
+
+/// 任一匹配时匹配，按顺序执行并在首个匹配处短路
+///
+/// 作为 PreHandler 使用时与 `Or` 相同，不匹配的 PreHandler 对 Session 的修改会传给之后的 PreHandler
+pub struct AnyOf<A>(pub A);
+
+/// 组合一组 Rule 或 PreHandler，全部匹配时匹配
+///
+/// `all_of((strip_whitespace(), strip_prefix("echo")))`
+pub fn all_of<A>(items: A) -> AllOf<A> {
+    AllOf(items)
+}
+
+/// 组合一组 Rule 或 PreHandler，任一匹配时匹配
+///
+/// `any_of((user_id_check("1"), user_id_check("2")))`
+pub fn any_of<A>(items: A) -> AnyOf<A> {
+    AnyOf(items)
+}
+
+impl<A, B, T, D, S, P, I> Rule<T, D, S, P, I> for And<A, B>
+where
+    A: Rule<T, D, S, P, I>,
+    B: Rule<T, D, S, P, I>,
+{
+    fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        match self.0.rule(session) {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & self.1.rule(session),
+        }
+    }
+}
+
+impl<A, B, T, D, S, P, I> Rule<T, D, S, P, I> for Or<A, B>
+where
+    A: Rule<T, D, S, P, I>,
+    B: Rule<T, D, S, P, I>,
+{
+    fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        match self.0.rule(session) {
+            Signal::NotMatch => self.1.rule(session),
+            sig => sig,
+        }
+    }
+}
+
+impl<A, T, D, S, P, I> Rule<T, D, S, P, I> for Not<A>
+where
+    A: Rule<T, D, S, P, I>,
+{
+    fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        !self.0.rule(session)
+    }
+}
+
+impl<A, B, T, D, S, P, I> PreHandler<T, D, S, P, I> for And<A, B>
+where
+    A: PreHandler<T, D, S, P, I>,
+    B: PreHandler<T, D, S, P, I>,
+{
+    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        match self.0.pre_handle(session) {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & self.1.pre_handle(session),
+        }
+    }
+}
+
+impl<A, B, T, D, S, P, I> PreHandler<T, D, S, P, I> for Or<A, B>
+where
+    A: PreHandler<T, D, S, P, I>,
+    B: PreHandler<T, D, S, P, I>,
+{
+    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        match self.0.pre_handle(session) {
+            Signal::NotMatch => self.1.pre_handle(session),
+            sig => sig,
+        }
+    }
+}
+
+impl<A, T, D, S, P, I> PreHandler<T, D, S, P, I> for Not<A>
+where
+    A: PreHandler<T, D, S, P, I>,
+{
+    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        !self.0.pre_handle(session)
+    }
+}
+
+#[async_trait]
+impl<A, B, T, D, S, P, I> AsyncRule<T, D, S, P, I> for AsyncAnd<A, B>
+where
+    A: AsyncRule<T, D, S, P, I>,
+    B: AsyncRule<T, D, S, P, I>,
+    T: Sync,
+    D: Sync,
+    S: Sync,
+    P: Sync,
+    I: Sync,
+{
+    async fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        match self.0.rule(session).await {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & self.1.rule(session).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<A, B, T, D, S, P, I> AsyncRule<T, D, S, P, I> for AsyncOr<A, B>
+where
+    A: AsyncRule<T, D, S, P, I>,
+    B: AsyncRule<T, D, S, P, I>,
+    T: Sync,
+    D: Sync,
+    S: Sync,
+    P: Sync,
+    I: Sync,
+{
+    async fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        match self.0.rule(session).await {
+            Signal::NotMatch => self.1.rule(session).await,
+            sig => sig,
+        }
+    }
+}
+
+#[async_trait]
+impl<A, T, D, S, P, I> AsyncRule<T, D, S, P, I> for AsyncNot<A>
+where
+    A: AsyncRule<T, D, S, P, I>,
+    T: Sync,
+    D: Sync,
+    S: Sync,
+    P: Sync,
+    I: Sync,
+{
+    async fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        !self.0.rule(session).await
+    }
+}
+
+#[async_trait]
+impl<A, B, T, D, S, P, I> AsyncPreHandler<T, D, S, P, I> for AsyncAnd<A, B>
+where
+    A: AsyncPreHandler<T, D, S, P, I>,
+    B: AsyncPreHandler<T, D, S, P, I>,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        match self.0.pre_handle(session).await {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & self.1.pre_handle(session).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<A, B, T, D, S, P, I> AsyncPreHandler<T, D, S, P, I> for AsyncOr<A, B>
+where
+    A: AsyncPreHandler<T, D, S, P, I>,
+    B: AsyncPreHandler<T, D, S, P, I>,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        match self.0.pre_handle(session).await {
+            Signal::NotMatch => self.1.pre_handle(session).await,
+            sig => sig,
+        }
+    }
+}
+
+#[async_trait]
+impl<A, T, D, S, P, I> AsyncPreHandler<T, D, S, P, I> for AsyncNot<A>
+where
+    A: AsyncPreHandler<T, D, S, P, I>,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        !self.0.pre_handle(session).await
+    }
+}
+
+/// 可被 `all_of` 和 `any_of` 组合的 Rule 元组
+pub trait RuleTuple<T = (), D = (), S = (), P = (), I = ()> {
+    fn all(&self, session: &Session<T, D, S, P, I>) -> Signal;
+    fn any(&self, session: &Session<T, D, S, P, I>) -> Signal;
+}
+
+/// 可被 `all_of` 和 `any_of` 组合的 PreHandler 元组
+pub trait PreHandlerTuple<T = (), D = (), S = (), P = (), I = ()> {
+    fn all(&self, session: &mut Session<T, D, S, P, I>) -> Signal;
+    fn any(&self, session: &mut Session<T, D, S, P, I>) -> Signal;
+}
+
+macro_rules! impl_tuple {
+    ($($idx: tt $ty: ident),+) => {
+        impl<$($ty,)+ T, D, S, P, I> RuleTuple<T, D, S, P, I> for ($($ty,)+)
+        where
+            $($ty: Rule<T, D, S, P, I>,)+
+        {
+            fn all(&self, session: &Session<T, D, S, P, I>) -> Signal {
+                let mut sig = Signal::Matched;
+                $(
+                    sig = sig & self.$idx.rule(session);
+                    if sig == Signal::NotMatch {
+                        return sig;
+                    }
+                )+
+                sig
+            }
+            fn any(&self, session: &Session<T, D, S, P, I>) -> Signal {
+                $(
+                    let sig = self.$idx.rule(session);
+                    if sig.is_matched() {
+                        return sig;
+                    }
+                )+
+                Signal::NotMatch
+            }
+        }
+
+        impl<$($ty,)+ T, D, S, P, I> PreHandlerTuple<T, D, S, P, I> for ($($ty,)+)
+        where
+            $($ty: PreHandler<T, D, S, P, I>,)+
+        {
+            fn all(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+                let mut sig = Signal::Matched;
+                $(
+                    sig = sig & self.$idx.pre_handle(session);
+                    if sig == Signal::NotMatch {
+                        return sig;
+                    }
+                )+
+                sig
+            }
+            fn any(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+                $(
+                    let sig = self.$idx.pre_handle(session);
+                    if sig.is_matched() {
+                        return sig;
+                    }
+                )+
+                Signal::NotMatch
+            }
+        }
+    };
+}
+
+impl_tuple!(0 A0);
+impl_tuple!(0 A0, 1 A1);
+impl_tuple!(0 A0, 1 A1, 2 A2);
+impl_tuple!(0 A0, 1 A1, 2 A2, 3 A3);
+impl_tuple!(0 A0, 1 A1, 2 A2, 3 A3, 4 A4);
+impl_tuple!(0 A0, 1 A1, 2 A2, 3 A3, 4 A4, 5 A5);
+impl_tuple!(0 A0, 1 A1, 2 A2, 3 A3, 4 A4, 5 A5, 6 A6);
+impl_tuple!(0 A0, 1 A1, 2 A2, 3 A3, 4 A4, 5 A5, 6 A6, 7 A7);
+
+impl<A, T, D, S, P, I> Rule<T, D, S, P, I> for AllOf<A>
+where
+    A: RuleTuple<T, D, S, P, I>,
+{
+    fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        self.0.all(session)
+    }
+}
+
+impl<A, T, D, S, P, I> Rule<T, D, S, P, I> for AnyOf<A>
+where
+    A: RuleTuple<T, D, S, P, I>,
+{
+    fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
+        self.0.any(session)
+    }
+}
+
+impl<A, T, D, S, P, I> PreHandler<T, D, S, P, I> for AllOf<A>
+where
+    A: PreHandlerTuple<T, D, S, P, I>,
+{
+    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        self.0.all(session)
+    }
+}
+
+impl<A, T, D, S, P, I> PreHandler<T, D, S, P, I> for AnyOf<A>
+where
+    A: PreHandlerTuple<T, D, S, P, I>,
+{
+    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        self.0.any(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session};
+    use crate::{PreHandler, Rule};
+    use walle_core::event::{Message, Private};
+
+    type PrivateSession = Session<Message, Private>;
+
+    struct Fixed(Signal);
+
+    impl Rule<Message, Private> for Fixed {
+        fn rule(&self, _: &PrivateSession) -> Signal {
+            self.0
+        }
+    }
+
+    impl PreHandler<Message, Private> for Fixed {
+        fn pre_handle(&self, _: &mut PrivateSession) -> Signal {
+            self.0
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRule<Message, Private> for Fixed {
+        async fn rule(&self, _: &PrivateSession) -> Signal {
+            self.0
+        }
+    }
+
+    /// 执行时 panic，用于验证短路
+    struct Unreachable;
+
+    fn unreachable_signal() -> Signal {
+        unreachable!("combinator did not short circuit")
+    }
+
+    impl Rule<Message, Private> for Unreachable {
+        fn rule(&self, _: &PrivateSession) -> Signal {
+            unreachable_signal()
+        }
+    }
+
+    impl PreHandler<Message, Private> for Unreachable {
+        fn pre_handle(&self, _: &mut PrivateSession) -> Signal {
+            unreachable_signal()
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRule<Message, Private> for Unreachable {
+        async fn rule(&self, _: &PrivateSession) -> Signal {
+            unreachable_signal()
+        }
+    }
+
+    fn new_session() -> PrivateSession {
+        session(message_event("1", "u", None, "hi"))
+    }
+
+    #[test]
+    fn signal_operators() {
+        use Signal::*;
+        assert_eq!(Matched + NotMatch, Matched);
+        assert_eq!(Matched + MatchAndBlock, MatchAndBlock);
+        assert_eq!(NotMatch + NotMatch, NotMatch);
+        assert_eq!(Matched & NotMatch, NotMatch);
+        assert_eq!(MatchAndBlock & NotMatch, NotMatch);
+        assert_eq!(Matched & MatchAndBlock, MatchAndBlock);
+        assert_eq!(Matched & Matched, Matched);
+        assert_eq!(Matched | MatchAndBlock, Matched);
+        assert_eq!(NotMatch | MatchAndBlock, MatchAndBlock);
+        assert_eq!(!MatchAndBlock, NotMatch);
+        assert_eq!(!NotMatch, Matched);
+    }
+
+    #[test]
+    fn sync_combinators_short_circuit() {
+        let mut session = new_session();
+        assert_eq!(
+            Rule::rule(&Rule::and(Fixed(Signal::NotMatch), Unreachable), &session),
+            Signal::NotMatch
+        );
+        assert_eq!(
+            Rule::rule(
+                &Rule::or(Fixed(Signal::MatchAndBlock), Unreachable),
+                &session
+            ),
+            Signal::MatchAndBlock
+        );
+        assert_eq!(
+            Rule::rule(&Rule::not(Fixed(Signal::Matched)), &session),
+            Signal::NotMatch
+        );
+        assert_eq!(
+            Rule::rule(
+                &all_of((Fixed(Signal::Matched), Fixed(Signal::NotMatch), Unreachable)),
+                &session
+            ),
+            Signal::NotMatch
+        );
+        assert_eq!(
+            PreHandler::pre_handle(
+                &any_of((Fixed(Signal::NotMatch), Fixed(Signal::Matched), Unreachable)),
+                &mut session
+            ),
+            Signal::Matched
+        );
+    }
+
+    #[test]
+    fn rule_with_pre_handler_requires_both() {
+        let mut session = new_session();
+        let joined = Fixed(Signal::Matched).with_pre_handler(Fixed(Signal::NotMatch));
+        assert_eq!(joined.pre_handle(&mut session), Signal::NotMatch);
+        let joined = PreHandler::with_rule(Fixed(Signal::MatchAndBlock), Fixed(Signal::Matched));
+        assert_eq!(joined.pre_handle(&mut session), Signal::MatchAndBlock);
+        let joined = PreHandler::with_rule(Unreachable, Fixed(Signal::NotMatch));
+        assert_eq!(joined.pre_handle(&mut session), Signal::NotMatch);
+    }
+
+    #[tokio::test]
+    async fn async_combinators_short_circuit() {
+        let session = new_session();
+        assert_eq!(
+            AsyncRule::rule(
+                &AsyncRule::and(Fixed(Signal::NotMatch), Unreachable),
+                &session
+            )
+            .await,
+            Signal::NotMatch
+        );
+        assert_eq!(
+            AsyncRule::rule(
+                &AsyncRule::or(Fixed(Signal::Matched), Unreachable),
+                &session
+            )
+            .await,
+            Signal::Matched
+        );
+        assert_eq!(
+            AsyncRule::rule(&AsyncRule::not(Fixed(Signal::NotMatch)), &session).await,
+            Signal::Matched
+        );
+    }
+
+    #[test]
+    fn with_joins_with_and() {
+        let mut session = new_session();
+        assert_eq!(
+            Rule::rule(&Rule::with(Fixed(Signal::NotMatch), Unreachable), &session),
+            Signal::NotMatch
+        );
+        assert_eq!(
+            Rule::rule(
+                &Rule::with(Fixed(Signal::Matched), Fixed(Signal::NotMatch)),
+                &session
+            ),
+            Signal::NotMatch
+        );
+        assert_eq!(
+            PreHandler::pre_handle(
+                &PreHandler::with(Fixed(Signal::Matched), Fixed(Signal::MatchAndBlock)),
+                &mut session
+            ),
+            Signal::MatchAndBlock
+        );
+    }
+}
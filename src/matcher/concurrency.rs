@@ -0,0 +1,274 @@
+use crate::{LimitScope, MatcherHandler, Session, Signal, Unconditioned};
+use dashmap::DashMap;
+use futures_util::future::{AbortHandle, AbortRegistration, Abortable};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use walle_core::prelude::async_trait;
+
+/// 超出并发上限时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcurrencyStrategy {
+    /// 排队等待
+    Queue,
+    /// 拒绝新事件，该事件继续交由更低优先级的 Matcher 处理
+    Reject,
+    /// 取消最早的任务
+    CancelOld,
+}
+
+/// 并发限制配置
+#[derive(Debug, Clone)]
+pub struct Concurrency {
+    pub limit: usize,
+    pub scope: LimitScope,
+    pub strategy: ConcurrencyStrategy,
+}
+
+impl Concurrency {
+    /// 同一作用域内至多 `limit` 个任务同时运行，默认全局计数并排队
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            scope: LimitScope::Global,
+            strategy: ConcurrencyStrategy::Queue,
+        }
+    }
+    /// 同一作用域内仅允许一个活跃任务，超出时拒绝
+    pub fn single_flight(scope: LimitScope) -> Self {
+        Self::new(1).scope(scope).reject()
+    }
+    pub fn scope(mut self, scope: LimitScope) -> Self {
+        self.scope = scope;
+        self
+    }
+    pub fn queue(mut self) -> Self {
+        self.strategy = ConcurrencyStrategy::Queue;
+        self
+    }
+    pub fn reject(mut self) -> Self {
+        self.strategy = ConcurrencyStrategy::Reject;
+        self
+    }
+    pub fn cancel_old(mut self) -> Self {
+        self.strategy = ConcurrencyStrategy::CancelOld;
+        self
+    }
+}
+
+/// 由 `Reject` 策略在 pre_handle 时获取，随 Session 释放
+struct ConcurrencyPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 并发限制层
+pub struct ConcurrencyLayer<H> {
+    pub concurrency: Concurrency,
+    pub handler: H,
+    semaphores: DashMap<String, Arc<Semaphore>>,
+    running: DashMap<String, Vec<(u64, AbortHandle)>>,
+    next_id: AtomicU64,
+}
+
+impl<H> ConcurrencyLayer<H> {
+    pub fn new(concurrency: Concurrency, handler: H) -> Self {
+        Self {
+            concurrency,
+            handler,
+            semaphores: DashMap::default(),
+            running: DashMap::default(),
+            next_id: AtomicU64::default(),
+        }
+    }
+    fn key<T, D, S, P, I>(&self, session: &Session<T, D, S, P, I>) -> String {
+        session
+            .origin
+            .as_ref()
+            .map(|origin| self.concurrency.scope.key(&origin.event))
+            .unwrap_or_default()
+    }
+    fn semaphore(&self, key: String) -> Arc<Semaphore> {
+        self.semaphores
+            .entry(key)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.concurrency.limit)))
+            .clone()
+    }
+    /// 登记新任务，并取消超出上限的最早任务
+    fn register(&self, key: &str) -> (u64, AbortRegistration) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (handle, registration) = AbortHandle::new_pair();
+        let mut running = self.running.entry(key.to_string()).or_default();
+        while running.len() >= self.concurrency.limit {
+            running.remove(0).1.abort();
+        }
+        running.push((id, handle));
+        (id, registration)
+    }
+    fn unregister(&self, key: &str, id: u64) {
+        if let Some(mut running) = self.running.get_mut(key) {
+            running.retain(|(task, _)| *task != id);
+        }
+        self.running.remove_if(key, |_, running| running.is_empty());
+    }
+    /// 没有任务持有或等待信号量时移除该作用域的信号量
+    fn release(&self, key: &str) {
+        self.semaphores
+            .remove_if(key, |_, semaphore| Arc::strong_count(semaphore) == 1);
+    }
+}
+
+#[async_trait]
+impl<H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for ConcurrencyLayer<H>
+where
+    H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send + 'static,
+    D: Send + 'static,
+    S: Send + 'static,
+    P: Send + 'static,
+    I: Send + 'static,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        let signal = self.handler.pre_handle(session).await;
+        if self.concurrency.strategy != ConcurrencyStrategy::Reject
+            || (signal == Signal::NotMatch && session.extensions.get::<Unconditioned>().is_none())
+        {
+            return signal;
+        }
+        match self.semaphore(self.key(session)).try_acquire_owned() {
+            Ok(permit) => {
+                session
+                    .extensions
+                    .insert(ConcurrencyPermit { _permit: permit });
+                signal
+            }
+            Err(_) => {
+                session.extensions.remove::<Unconditioned>();
+                Signal::NotMatch
+            }
+        }
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
+    fn handle<'a, 't>(
+        &'a self,
+        session: Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 't>>
+    where
+        'a: 't,
+        Self: 't,
+    {
+        let key = self.key(&session);
+        match self.concurrency.strategy {
+            ConcurrencyStrategy::Reject => Box::pin(async move {
+                // Session 随 Handler 结束释放，其中的许可一并归还
+                self.handler.handle(session).await;
+                self.release(&key);
+            }),
+            ConcurrencyStrategy::Queue => {
+                let semaphore = self.semaphore(key.clone());
+                Box::pin(async move {
+                    if let Ok(_permit) = semaphore.acquire_owned().await {
+                        self.handler.handle(session).await;
+                    }
+                    self.release(&key);
+                })
+            }
+            ConcurrencyStrategy::CancelOld => {
+                let (id, registration) = self.register(&key);
+                Box::pin(async move {
+                    Abortable::new(self.handler.handle(session), registration)
+                        .await
+                        .ok();
+                    self.unregister(&key, id);
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session};
+    use crate::{handler_fn, layered_pre_handle};
+    use walle_core::event::{Message, Private};
+
+    fn layer(concurrency: Concurrency) -> ConcurrencyLayer<impl MatcherHandler<Message, Private>> {
+        ConcurrencyLayer::new(
+            concurrency,
+            handler_fn(|_: Session<Message, Private>| async {}),
+        )
+    }
+
+    fn new_session() -> Session<Message, Private> {
+        session(message_event("1", "u", None, "hi"))
+    }
+
+    #[tokio::test]
+    async fn released_scopes_are_removed() {
+        for concurrency in [Concurrency::new(1), Concurrency::new(1).reject()] {
+            let layer = layer(concurrency);
+            let mut session = new_session();
+            assert_eq!(
+                layered_pre_handle(&layer, &mut session).await,
+                Signal::Matched
+            );
+            layer.handle(session).await;
+            assert!(layer.semaphores.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn finished_tasks_are_unregistered() {
+        let layer = layer(Concurrency::new(1).cancel_old());
+        layer.handle(new_session()).await;
+        assert!(layer.running.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reject_holds_permit_until_released() {
+        let layer = layer(Concurrency::single_flight(LimitScope::User));
+        let mut first = new_session();
+        assert_eq!(
+            layered_pre_handle(&layer, &mut first).await,
+            Signal::Matched
+        );
+        let mut second = new_session();
+        assert_eq!(
+            layered_pre_handle(&layer, &mut second).await,
+            Signal::NotMatch
+        );
+        layer.handle(first).await;
+        let mut third = new_session();
+        assert_eq!(
+            layered_pre_handle(&layer, &mut third).await,
+            Signal::Matched
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{builtin::plain_text, ReplyAbleSession, Session, WaitError};
+use std::{fmt, str::FromStr, time::Duration};
+use walle_core::{
+    event::Message,
+    segment::{IntoMessage, Segments},
+    WalleError,
+};
+
+/// 多轮对话中单次回答的校验结果
+pub enum Verdict<T> {
+    /// 接受回答
+    Accept(T),
+    /// 回复消息并重新等待回答
+    Reject(Segments),
+    /// 回复消息并结束对话
+    Finish(Segments),
+}
+
+impl<T> Verdict<T> {
+    pub fn accept(value: T) -> Self {
+        Self::Accept(value)
+    }
+    pub fn reject<M: IntoMessage>(message: M) -> Self {
+        Self::Reject(message.into_message())
+    }
+    pub fn finish<M: IntoMessage>(message: M) -> Self {
+        Self::Finish(message.into_message())
+    }
+}
+
+/// 多轮对话未得到回答的原因
+#[derive(Debug)]
+pub enum ConversationError {
+    /// 等待回答超时
+    Timeout,
+    /// 用户发送了 `MatchersConfig::cancel_keywords` 中的关键词，或等待被取消
+    Cancelled,
+    /// 校验函数返回了 `Verdict::Finish`
+    Finished,
+    /// 回答被拒绝的次数超过 `Prompt::max_retries`
+    RetriesExhausted,
+    Walle(WalleError),
+}
+
+impl fmt::Display for ConversationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "conversation timeout"),
+            Self::Cancelled => write!(f, "conversation cancelled"),
+            Self::Finished => write!(f, "conversation finished"),
+            Self::RetriesExhausted => write!(f, "conversation retries exhausted"),
+            Self::Walle(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConversationError {}
+
+impl From<WaitError> for ConversationError {
+    fn from(e: WaitError) -> Self {
+        match e {
+            WaitError::Timeout => Self::Timeout,
+            WaitError::Cancelled => Self::Cancelled,
+            WaitError::Walle(e) => Self::Walle(e),
+        }
+    }
+}
+
+impl From<WalleError> for ConversationError {
+    fn from(e: WalleError) -> Self {
+        Self::Walle(e)
+    }
+}
+
+impl From<ConversationError> for String {
+    fn from(e: ConversationError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 多轮对话中的一次提问
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub message: Segments,
+    /// 每次等待回答的时长，为 None 时使用 `ReplyAbleSession::get` 的默认值
+    pub timeout: Option<Duration>,
+    /// 回答被拒绝后至多重新提问的次数
+    pub max_retries: usize,
+    /// `Session::prompt` 解析失败时的回复
+    pub retry_message: String,
+}
+
+impl Prompt {
+    pub fn new<M: IntoMessage>(message: M) -> Self {
+        Self {
+            message: message.into_message(),
+            timeout: None,
+            max_retries: 3,
+            retry_message: "格式错误，请重新输入".to_string(),
+        }
+    }
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+    pub fn retry_message(mut self, message: &str) -> Self {
+        self.retry_message = message.to_string();
+        self
+    }
+}
+
+impl<D, S, P, I> Session<Message, D, S, P, I>
+where
+    Self: ReplyAbleSession + Send,
+{
+    /// 提问并等待回答，直到 `validator` 接受回答
+    ///
+    /// 回答为取消关键词时中止对话，`Verdict::Reject` 时回复并重新提问，超过重试次数时回复后结束对话，
+    /// `Verdict::Finish` 时回复并结束对话
+    pub async fn prompt_until<T, F>(
+        &mut self,
+        prompt: Prompt,
+        mut validator: F,
+    ) -> Result<T, ConversationError>
+    where
+        F: FnMut(&Self) -> Verdict<T> + Send,
+    {
+        let mut retries = 0;
+        loop {
+            self.get(prompt.message.clone(), prompt.timeout).await?;
+            match validator(self) {
+                Verdict::Accept(value) => return Ok(value),
+                Verdict::Reject(reject) => {
+                    self.send(reject).await?;
+                    if retries >= prompt.max_retries {
+                        return Err(ConversationError::RetriesExhausted);
+                    }
+                    retries += 1;
+                }
+                Verdict::Finish(finish) => {
+                    self.send(finish).await?;
+                    return Err(ConversationError::Finished);
+                }
+            }
+        }
+    }
+    /// 提问并将回答的文本解析为 `T`，解析失败时以 `Prompt::retry_message` 重新提问
+    pub async fn prompt<T>(&mut self, prompt: Prompt) -> Result<T, ConversationError>
+    where
+        T: FromStr,
+    {
+        let retry_message = prompt.retry_message.clone();
+        self.prompt_until(prompt, |session| match plain_text(session).trim().parse() {
+            Ok(value) => Verdict::Accept(value),
+            Err(_) => Verdict::reject(retry_message.as_str()),
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::on_command;
+    use crate::testing::{message_event, DummyCaller};
+    use crate::{handler_fn, MatcherHandlerExt, MatchersConfig, MatchersHandle};
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+    use walle_core::event::Private;
+
+    /// 以 `ask` 触发 `Session::prompt::<u32>`，依次发送 `answers`，返回对话结果与 bot 发送的文本
+    async fn ask(answers: &[&str]) -> (Result<u32, ConversationError>, Vec<String>) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let handle = MatchersHandle::default();
+        handle
+            .insert(
+                on_command(
+                    "ask",
+                    handler_fn(move |mut s: Session<Message, Private>| {
+                        let tx = tx.clone();
+                        async move {
+                            let prompt = Prompt::new("数量？")
+                                .max_retries(1)
+                                .timeout(Duration::from_secs(5));
+                            tx.send(s.prompt::<u32>(prompt).await).ok();
+                        }
+                    }),
+                )
+                .boxed(),
+            )
+            .await;
+        let (dummy, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        handle
+            .call(&message_event("0", "u", None, "ask"), &config, &caller)
+            .await;
+        let mut answered = None;
+        for (i, answer) in answers.iter().enumerate() {
+            // 等待新一轮提问注册临时 Matcher
+            answered = tokio::time::timeout(Duration::from_secs(1), async {
+                loop {
+                    let waits = handle.pending_waits("u");
+                    if let Some(wait) = waits
+                        .iter()
+                        .find(|wait| Some(&wait.id) != answered.as_ref())
+                    {
+                        return Some(wait.id.clone());
+                    }
+                    tokio::task::yield_now().await;
+                }
+            })
+            .await
+            .unwrap();
+            handle
+                .call(
+                    &message_event(&(i + 1).to_string(), "u", None, answer),
+                    &config,
+                    &caller,
+                )
+                .await;
+        }
+        let result = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        (result, dummy.sent_texts())
+    }
+
+    #[tokio::test]
+    async fn reject_asks_again() {
+        let (result, texts) = ask(&["x", "3"]).await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(texts, ["数量？", "格式错误，请重新输入", "数量？"]);
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_are_replied() {
+        let (result, texts) = ask(&["x", "y"]).await;
+        assert!(matches!(result, Err(ConversationError::RetriesExhausted)));
+        assert_eq!(
+            texts,
+            [
+                "数量？",
+                "格式错误，请重新输入",
+                "数量？",
+                "格式错误，请重新输入"
+            ]
+        );
+    }
+
+    #[test]
+    fn only_wait_timeout_is_timeout() {
+        assert!(matches!(
+            ConversationError::from(WaitError::Timeout),
+            ConversationError::Timeout
+        ));
+        assert!(matches!(
+            ConversationError::from(WaitError::Cancelled),
+            ConversationError::Cancelled
+        ));
+        assert!(matches!(
+            ConversationError::from(WalleError::ResponseTimeout),
+            ConversationError::Walle(WalleError::ResponseTimeout)
+        ));
+    }
+}
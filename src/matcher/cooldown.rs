@@ -0,0 +1,374 @@
+use super::report::reply_event;
+use crate::{MatcherHandler, Session, Signal, Unconditioned};
+use dashmap::DashMap;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+use walle_core::{
+    event::Event,
+    prelude::{async_trait, GetSelf},
+    util::ValueMapExt,
+};
+
+/// 冷却与并发限制的计数作用域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitScope {
+    User,
+    /// 私聊时按用户计数
+    Group,
+    Bot,
+    Global,
+}
+
+impl LimitScope {
+    pub(crate) fn key(&self, event: &Event) -> String {
+        let get = |key: &str| event.extra.get_downcast::<String>(key).ok();
+        match self {
+            Self::User => format!("user:{}", get("user_id").unwrap_or_default()),
+            Self::Group => match get("group_id") {
+                Some(group_id) => format!("group:{}", group_id),
+                None => format!("user:{}", get("user_id").unwrap_or_default()),
+            },
+            Self::Bot => {
+                let selft = event.get_self();
+                format!("bot:{}:{}", selft.platform, selft.user_id)
+            }
+            Self::Global => String::default(),
+        }
+    }
+}
+
+/// 限流算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimit {
+    /// 令牌桶，容量为 `capacity`，每隔 `refill` 补充一个令牌
+    TokenBucket { capacity: u32, refill: Duration },
+    /// 固定窗口，每个 `window` 内至多 `limit` 次
+    FixedWindow { limit: u32, window: Duration },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LimitState {
+    TokenBucket { tokens: f64, last: Instant },
+    FixedWindow { count: u32, start: Instant },
+}
+
+impl RateLimit {
+    fn init(&self, now: Instant) -> LimitState {
+        match self {
+            Self::TokenBucket { capacity, .. } => LimitState::TokenBucket {
+                tokens: *capacity as f64,
+                last: now,
+            },
+            Self::FixedWindow { .. } => LimitState::FixedWindow {
+                count: 0,
+                start: now,
+            },
+        }
+    }
+    /// 状态在最后一次使用后经过该时长即恢复为初始状态
+    fn idle_after(&self) -> Duration {
+        match self {
+            Self::TokenBucket { capacity, refill } => refill.saturating_mul(*capacity),
+            Self::FixedWindow { window, .. } => *window,
+        }
+    }
+    fn is_idle(&self, state: &LimitState, now: Instant) -> bool {
+        let last = match state {
+            LimitState::TokenBucket { last, .. } => last,
+            LimitState::FixedWindow { start, .. } => start,
+        };
+        now.duration_since(*last) >= self.idle_after()
+    }
+    /// 消耗一次额度，超出限制时返回需要等待的时间
+    fn acquire(&self, state: &mut LimitState, now: Instant) -> Result<(), Duration> {
+        match (self, state) {
+            (Self::TokenBucket { capacity, refill }, LimitState::TokenBucket { tokens, last }) => {
+                let refill = refill.as_secs_f64().max(f64::EPSILON);
+                *tokens = (*tokens + now.duration_since(*last).as_secs_f64() / refill)
+                    .min(*capacity as f64);
+                *last = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    Ok(())
+                } else {
+                    Err(Duration::from_secs_f64((1.0 - *tokens) * refill))
+                }
+            }
+            (Self::FixedWindow { limit, window }, LimitState::FixedWindow { count, start }) => {
+                if now.duration_since(*start) >= *window {
+                    *count = 0;
+                    *start = now;
+                }
+                if *count < *limit {
+                    *count += 1;
+                    Ok(())
+                } else {
+                    Err(*window - now.duration_since(*start))
+                }
+            }
+            (_, state) => {
+                *state = self.init(now);
+                self.acquire(state, now)
+            }
+        }
+    }
+}
+
+/// 冷却配置
+#[derive(Debug, Clone)]
+pub struct Cooldown {
+    pub limit: RateLimit,
+    pub scope: LimitScope,
+    /// 触发限制时的回复，`{secs}` 替换为剩余秒数，为 None 时不回复
+    pub reply: Option<String>,
+}
+
+impl Cooldown {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            scope: LimitScope::User,
+            reply: None,
+        }
+    }
+    /// 令牌桶，可连续触发 `capacity` 次，之后每隔 `refill` 恢复一次
+    pub fn token_bucket(capacity: u32, refill: Duration) -> Self {
+        Self::new(RateLimit::TokenBucket { capacity, refill })
+    }
+    /// 固定窗口，每个 `window` 内至多触发 `limit` 次
+    pub fn fixed_window(limit: u32, window: Duration) -> Self {
+        Self::new(RateLimit::FixedWindow { limit, window })
+    }
+    /// 计数作用域，默认为 `LimitScope::User`
+    pub fn scope(mut self, scope: LimitScope) -> Self {
+        self.scope = scope;
+        self
+    }
+    pub fn reply(mut self, reply: &str) -> Self {
+        self.reply = Some(reply.to_string());
+        self
+    }
+}
+
+/// 冷却层，超出限制时不执行 Handler
+pub struct CooldownLayer<H> {
+    pub cooldown: Cooldown,
+    pub handler: H,
+    states: DashMap<String, LimitState>,
+    last_sweep: Mutex<Instant>,
+}
+
+impl<H> CooldownLayer<H> {
+    pub fn new(cooldown: Cooldown, handler: H) -> Self {
+        Self {
+            cooldown,
+            handler,
+            states: DashMap::default(),
+            last_sweep: Mutex::new(Instant::now()),
+        }
+    }
+    fn acquire(&self, event: &Event) -> Result<(), Duration> {
+        let now = Instant::now();
+        let limit = &self.cooldown.limit;
+        let result = limit.acquire(
+            &mut self
+                .states
+                .entry(self.cooldown.scope.key(event))
+                .or_insert_with(|| limit.init(now)),
+            now,
+        );
+        self.sweep(now);
+        result
+    }
+    /// 每隔 `RateLimit::idle_after` 移除已恢复为初始状态的计数
+    fn sweep(&self, now: Instant) {
+        let limit = &self.cooldown.limit;
+        {
+            let mut last_sweep = self.last_sweep.lock().unwrap();
+            if now.duration_since(*last_sweep) < limit.idle_after() {
+                return;
+            }
+            *last_sweep = now;
+        }
+        self.states.retain(|_, state| !limit.is_idle(state, now));
+    }
+}
+
+#[async_trait]
+impl<H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for CooldownLayer<H>
+where
+    H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send + 'static,
+    D: Send + 'static,
+    S: Send + 'static,
+    P: Send + 'static,
+    I: Send + 'static,
+{
+    /// 内层匹配后消耗一次额度，超出限制时回复并返回 `Signal::NotMatch`，不阻止更低优先级的 Matcher
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        let signal = self.handler.pre_handle(session).await;
+        if signal == Signal::NotMatch && session.extensions.get::<Unconditioned>().is_none() {
+            return signal;
+        }
+        let Some(origin) = session.origin.clone() else {
+            return signal;
+        };
+        let Err(wait) = self.acquire(&origin.event) else {
+            return signal;
+        };
+        session.extensions.remove::<Unconditioned>();
+        if let Some(reply) = &self.cooldown.reply {
+            let reply = reply.replace("{secs}", &wait.as_secs().max(1).to_string());
+            let caller = session.caller.clone();
+            tokio::spawn(async move {
+                if let Err(e) = reply_event(&caller, &origin.event, &reply).await {
+                    warn!(target: "Walle", "reply cooldown message failed: {}", e);
+                }
+            });
+        }
+        Signal::NotMatch
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
+    fn handle<'a, 't>(
+        &'a self,
+        session: Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 't>>
+    where
+        'a: 't,
+        Self: 't,
+    {
+        self.handler.handle(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, DummyCaller};
+    use crate::{handler_fn, rule_fn, MatcherHandlerExt, MatchersConfig, MatchersHandle, Rule};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use walle_core::event::{Message, Private};
+
+    fn counting(count: &Arc<AtomicUsize>, signal: Signal) -> impl MatcherHandler<Message, Private> {
+        let count = count.clone();
+        rule_fn(move |_: &Session<Message, Private>| signal).layer(handler_fn(
+            move |_: Session<Message, Private>| {
+                count.fetch_add(1, Ordering::SeqCst);
+                async {}
+            },
+        ))
+    }
+
+    #[test]
+    fn token_bucket_refills() {
+        let limit = RateLimit::TokenBucket {
+            capacity: 2,
+            refill: Duration::from_secs(10),
+        };
+        let now = Instant::now();
+        let mut state = limit.init(now);
+        assert!(limit.acquire(&mut state, now).is_ok());
+        assert!(limit.acquire(&mut state, now).is_ok());
+        assert_eq!(limit.acquire(&mut state, now), Err(Duration::from_secs(10)));
+        let later = now + Duration::from_secs(5);
+        assert_eq!(
+            limit.acquire(&mut state, later),
+            Err(Duration::from_secs(5))
+        );
+        assert!(limit
+            .acquire(&mut state, now + Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[test]
+    fn fixed_window_resets() {
+        let limit = RateLimit::FixedWindow {
+            limit: 1,
+            window: Duration::from_secs(10),
+        };
+        let now = Instant::now();
+        let mut state = limit.init(now);
+        assert!(limit.acquire(&mut state, now).is_ok());
+        assert_eq!(
+            limit.acquire(&mut state, now + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+        assert!(limit
+            .acquire(&mut state, now + Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[test]
+    fn idle_states_are_swept() {
+        let layer = CooldownLayer::new(Cooldown::fixed_window(1, Duration::from_millis(10)), ());
+        for user in ["a", "b", "c"] {
+            assert!(layer.acquire(&message_event("1", user, None, "")).is_ok());
+        }
+        assert_eq!(layer.states.len(), 3);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(layer.acquire(&message_event("2", "d", None, "")).is_ok());
+        assert_eq!(layer.states.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn throttled_event_falls_through() {
+        let (limited, fallback) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let handle = MatchersHandle::default();
+        handle
+            .insert(
+                counting(&limited, Signal::MatchAndBlock)
+                    .with_cooldown(
+                        Cooldown::fixed_window(1, Duration::from_secs(60)).reply("冷却中"),
+                    )
+                    .boxed()
+                    .name("limited")
+                    .priority(0)
+                    .times(2),
+            )
+            .await;
+        handle
+            .insert(counting(&fallback, Signal::Matched).boxed().priority(1))
+            .await;
+        let (dummy, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        for id in ["1", "2"] {
+            handle
+                .call(&message_event(id, "u", None, "hi"), &config, &caller)
+                .await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(limited.load(Ordering::SeqCst), 1);
+        assert_eq!(fallback.load(Ordering::SeqCst), 1);
+        assert!(handle.contains("limited").await);
+        assert_eq!(dummy.sent_texts(), ["冷却中"]);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{ActionCaller, IntoReply, MatchersConfig, MatchersHandle, Session, SessionOrigin};
+use std::{future::Future, pin::Pin, sync::Arc};
+use walle_core::{
+    event::{
+        BaseEvent, DetailTypeLevel, ImplLevel, ParseEvent, PlatformLevel, SubTypeLevel, TypeLevel,
+    },
+    prelude::TryFromEvent,
+};
+
+/// 从 Session 中提取 Handler 参数，提取失败时 Matcher 视为不匹配
+pub trait FromSession<T = (), D = (), S = (), P = (), I = ()>: Sized {
+    fn from_session(session: &Session<T, D, S, P, I>) -> Option<Self>;
+}
+
+/// 可选参数，总是提取成功
+impl<E, T, D, S, P, I> FromSession<T, D, S, P, I> for Option<E>
+where
+    E: FromSession<T, D, S, P, I>,
+{
+    fn from_session(session: &Session<T, D, S, P, I>) -> Option<Self> {
+        Some(E::from_session(session))
+    }
+}
+
+impl<T, D, S, P, I> FromSession<T, D, S, P, I> for Arc<MatchersConfig> {
+    fn from_session(session: &Session<T, D, S, P, I>) -> Option<Self> {
+        Some(session.config.clone())
+    }
+}
+
+/// 可用于 `handler_fn` 的函数，首个参数为 Session，其余参数为 FromSession 提取器，
+/// 返回值通过 IntoReply 回复
+pub trait SessionFn<A, T = (), D = (), S = (), P = (), I = ()>: Send + Sync + 'static {
+    /// 提取所有参数并暂存于 `Session::extensions`，任一参数提取失败时返回 false
+    fn extract(session: &mut Session<T, D, S, P, I>) -> bool;
+    fn call(&self, session: Session<T, D, S, P, I>) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// `SessionFn::extract` 暂存的参数
+struct Extracted<A>(A);
+
+/// 由 `SessionOrigin` 重新解析事件，构造回复使用的 Session
+fn reply_session<T, D, S, P, I>(
+    origin: Option<Arc<SessionOrigin>>,
+    caller: Arc<dyn ActionCaller + Send + 'static>,
+    config: Arc<MatchersConfig>,
+    matchers: MatchersHandle,
+) -> Option<Session<T, D, S, P, I>>
+where
+    T: TryFromEvent<TypeLevel>,
+    D: TryFromEvent<DetailTypeLevel>,
+    S: TryFromEvent<SubTypeLevel>,
+    I: TryFromEvent<ImplLevel>,
+    P: TryFromEvent<PlatformLevel>,
+{
+    let origin = origin?;
+    let event = BaseEvent::parse(origin.event.clone(), &origin.implt).ok()?;
+    let mut session = Session::new(event, caller, config, matchers);
+    session.origin = Some(origin);
+    Some(session)
+}
+
+macro_rules! impl_session_fn {
+    ($($ty: ident $arg: ident),*) => {
+        impl<F, Fut, R, $($ty,)* T, D, S, P, I> SessionFn<($($ty,)*), T, D, S, P, I> for F
+        where
+            F: Fn(Session<T, D, S, P, I>, $($ty),*) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = R> + Send + 'static,
+            R: IntoReply<T, D, S, P, I> + 'static,
+            $($ty: FromSession<T, D, S, P, I> + Send + Sync + 'static,)*
+            T: TryFromEvent<TypeLevel> + Send + Sync + 'static,
+            D: TryFromEvent<DetailTypeLevel> + Send + Sync + 'static,
+            S: TryFromEvent<SubTypeLevel> + Send + Sync + 'static,
+            P: TryFromEvent<PlatformLevel> + Send + Sync + 'static,
+            I: TryFromEvent<ImplLevel> + Send + Sync + 'static,
+        {
+            fn extract(session: &mut Session<T, D, S, P, I>) -> bool {
+                $(
+                    let Some($arg) = $ty::from_session(session) else {
+                        return false;
+                    };
+                )*
+                session.extensions.insert(Extracted(($($arg,)*)));
+                true
+            }
+            fn call(
+                &self,
+                mut session: Session<T, D, S, P, I>,
+            ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+                // 未经过 prepare 时重新提取
+                let Some(Extracted(($($arg,)*))) = session
+                    .extensions
+                    .take::<Extracted<($($ty,)*)>>()
+                    .or_else(|| {
+                        Self::extract(&mut session)
+                            .then(|| session.extensions.take())
+                            .flatten()
+                    })
+                else {
+                    return Box::pin(async {});
+                };
+                let (origin, caller, config, matchers) = (
+                    session.origin.clone(),
+                    session.caller.clone(),
+                    session.config.clone(),
+                    session.matchers.clone(),
+                );
+                let fut = self(session, $($arg),*);
+                Box::pin(async move {
+                    let reply = fut.await;
+                    if let Some(session) = reply_session(origin, caller, config, matchers) {
+                        reply.reply(&session).await;
+                    }
+                })
+            }
+        }
+    };
+}
+
+impl_session_fn!();
+impl_session_fn!(A0 a0);
+impl_session_fn!(A0 a0, A1 a1);
+impl_session_fn!(A0 a0, A1 a1, A2 a2);
+impl_session_fn!(A0 a0, A1 a1, A2 a2, A3 a3);
+impl_session_fn!(A0 a0, A1 a1, A2 a2, A3 a3, A4 a4);
+impl_session_fn!(A0 a0, A1 a1, A2 a2, A3 a3, A4 a4, A5 a5);
+impl_session_fn!(A0 a0, A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6);
+impl_session_fn!(A0 a0, A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6, A7 a7);
@@ -1,9 +1,25 @@
-use crate::{ActionCaller, Matcher, MatchersConfig, ReplyAbleSession, TempMatchers};
+use crate::{
+    ActionCaller, AsyncPermissionLayer, Concurrency, ConcurrencyLayer, Cooldown, CooldownLayer,
+    ErrorReport, IntoReply, Matcher, MatcherError, MatchersConfig, MatchersHandle, PermissionLayer,
+    SessionOrigin,
+};
 
-use super::{LayeredPreHandler, LayeredRule, PreHandler, Rule, Session};
-use std::{future::Future, pin::Pin, sync::Arc};
+use super::{
+    AsyncPreHandler, AsyncRule, LayeredAsyncPreHandler, LayeredAsyncRule, LayeredPreHandler,
+    LayeredRule, PreHandler, Rule, Session, SessionFn,
+};
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use async_trait::async_trait;
+use futures_util::FutureExt;
 use walle_core::{
     event::{
         BaseEvent, DetailTypeLevel, Event, ImplLevel, ParseEvent, PlatformLevel, SubTypeLevel,
@@ -14,13 +30,20 @@ use walle_core::{
     util::GetSelf,
 };
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Signal {
     MatchAndBlock,
     Matched,
     NotMatch,
 }
 
+impl Signal {
+    pub fn is_matched(&self) -> bool {
+        *self != Self::NotMatch
+    }
+}
+
+/// 任一匹配即匹配，任一 MatchAndBlock 即 MatchAndBlock
 impl core::ops::Add for Signal {
     type Output = Self;
     fn add(self, rhs: Self) -> Self::Output {
@@ -32,15 +55,63 @@ impl core::ops::Add for Signal {
     }
 }
 
+/// 逻辑与：任一 NotMatch 即 NotMatch，否则任一 MatchAndBlock 即 MatchAndBlock
+impl core::ops::BitAnd for Signal {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (_, Self::NotMatch) | (Self::NotMatch, _) => Self::NotMatch,
+            (_, Self::MatchAndBlock) | (Self::MatchAndBlock, _) => Self::MatchAndBlock,
+            _ => Self::Matched,
+        }
+    }
+}
+
+/// 逻辑或：返回第一个匹配的 Signal，与短路求值的结果保持一致
+impl core::ops::BitOr for Signal {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        if self.is_matched() {
+            self
+        } else {
+            rhs
+        }
+    }
+}
+
+/// 逻辑非：匹配（包括 MatchAndBlock）变为 NotMatch，NotMatch 变为 Matched
+impl core::ops::Not for Signal {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        if self.is_matched() {
+            Self::NotMatch
+        } else {
+            Self::Matched
+        }
+    }
+}
+
 #[async_trait]
 pub trait RawMatcherHandler {
+    /// `remaining` 为 Matcher 剩余使用次数，匹配后须先预留一次才会执行 Handler，
+    /// 预留失败时返回 `Signal::NotMatch`
     async fn call(
         &self,
         event: Event,
         config: &Arc<MatchersConfig>,
         caller: &Arc<dyn ActionCaller + Send + 'static>,
-        temp: &TempMatchers,
+        matchers: &MatchersHandle,
+        name: Option<&str>,
+        remaining: Option<&AtomicUsize>,
     ) -> Signal;
+    /// 事件发送者是否有权限使用该 Matcher，事件类型不匹配时返回 false
+    async fn permit(
+        &self,
+        event: Event,
+        config: &Arc<MatchersConfig>,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        matchers: &MatchersHandle,
+    ) -> bool;
 }
 
 pub(crate) struct BoxedHandler<H, T, D, S, P, I>(
@@ -48,6 +119,36 @@ pub(crate) struct BoxedHandler<H, T, D, S, P, I>(
     pub std::marker::PhantomData<(T, D, S, P, I)>,
 );
 
+impl<H, T, D, S, P, I> BoxedHandler<H, T, D, S, P, I>
+where
+    T: TryFromEvent<TypeLevel>,
+    D: TryFromEvent<DetailTypeLevel>,
+    S: TryFromEvent<SubTypeLevel>,
+    I: TryFromEvent<ImplLevel>,
+    P: TryFromEvent<PlatformLevel>,
+{
+    async fn session(
+        event: Event,
+        config: &Arc<MatchersConfig>,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        matchers: &MatchersHandle,
+    ) -> Option<Session<T, D, S, P, I>> {
+        let implt = caller.get_impl(&event.get_self()).await;
+        Self::parse(event, &implt, config, caller, matchers)
+    }
+    fn parse(
+        event: Event,
+        implt: &str,
+        config: &Arc<MatchersConfig>,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        matchers: &MatchersHandle,
+    ) -> Option<Session<T, D, S, P, I>> {
+        BaseEvent::<T, D, S, P, I>::parse(event, implt)
+            .ok()
+            .map(|event| Session::new(event, caller.clone(), config.clone(), matchers.clone()))
+    }
+}
+
 #[async_trait]
 impl<H, T, D, S, P, I> RawMatcherHandler for BoxedHandler<H, T, D, S, P, I>
 where
@@ -63,36 +164,135 @@ where
         event: Event,
         config: &Arc<MatchersConfig>,
         caller: &Arc<dyn ActionCaller + Send + 'static>,
-        temp: &TempMatchers,
+        matchers: &MatchersHandle,
+        name: Option<&str>,
+        remaining: Option<&AtomicUsize>,
     ) -> Signal {
         let implt = caller.get_impl(&event.get_self()).await;
-        match BaseEvent::<T, D, S, P, I>::parse(event, &implt) {
-            Ok(event) => {
-                let mut session = Session::<T, D, S, P, I>::new(
-                    event,
-                    caller.clone(),
-                    config.clone(),
-                    temp.clone(),
-                );
-                let signal = self.0.pre_handle(&mut session);
-                let handler = self.0.clone();
-                if signal != Signal::NotMatch {
-                    tokio::spawn(async move {
-                        handler.handle(session).await;
-                    });
+        let origin = SessionOrigin {
+            matcher: name.map(ToString::to_string),
+            event: event.clone(),
+            implt: implt.clone(),
+        };
+        match Self::parse(event, &implt, config, caller, matchers) {
+            Some(mut session) => {
+                session.origin = Some(Arc::new(origin.clone()));
+                let signal = match self.0.pre_handle(&mut session).await {
+                    Signal::NotMatch => Signal::NotMatch,
+                    sig => sig & self.0.prepare(&mut session).await,
+                };
+                if signal == Signal::NotMatch {
+                    return signal;
+                }
+                // 先预留使用次数，避免并发事件使 times(n) 的 Matcher 多执行
+                if let Some(remaining) = remaining {
+                    if remaining
+                        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+                        .is_err()
+                    {
+                        return Signal::NotMatch;
+                    }
                 }
+                let handler = self.0.clone();
+                let (matchers, caller, config) = (
+                    session.matchers.clone(),
+                    session.caller.clone(),
+                    session.config.clone(),
+                );
+                tokio::spawn(async move {
+                    if let Err(payload) = AssertUnwindSafe(handler.handle(session))
+                        .catch_unwind()
+                        .await
+                    {
+                        let report = ErrorReport {
+                            matcher: origin.matcher,
+                            event: origin.event,
+                            error: MatcherError::from_panic(payload),
+                        };
+                        matchers.report(report, &caller, &config).await;
+                    }
+                });
                 signal
             }
-            Err(_) => Signal::NotMatch,
+            None => Signal::NotMatch,
+        }
+    }
+    async fn permit(
+        &self,
+        event: Event,
+        config: &Arc<MatchersConfig>,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        matchers: &MatchersHandle,
+    ) -> bool {
+        match Self::session(event, config, caller, matchers).await {
+            Some(session) => self.0.permit(&session).await,
+            None => false,
         }
     }
 }
 
+/// 未重写 `MatcherHandler::pre_handle` 的 Handler 在 Session 中留下的标记
+pub(crate) struct Unconditioned;
+
+/// 执行内层 Handler 的 pre_handle，供 Rule、PreHandler 等层与外层按逻辑与组合
+///
+/// 未重写 pre_handle 的 Handler 不附加匹配条件，视为 `Signal::Matched`
+pub async fn layered_pre_handle<H, T, D, S, P, I>(
+    handler: &H,
+    session: &mut Session<T, D, S, P, I>,
+) -> Signal
+where
+    H: MatcherHandler<T, D, S, P, I> + ?Sized,
+{
+    let signal = handler.pre_handle(session).await;
+    if session.extensions.remove::<Unconditioned>() {
+        Signal::Matched
+    } else {
+        signal
+    }
+}
+
 /// Matcher Handler
+///
+/// `pre_handle` 默认返回 `Signal::NotMatch`，未添加 Rule 或 PreHandler 的 Handler 不匹配任何事件；
+/// 作为 Rule、PreHandler 等层的内层时不附加匹配条件，见 `layered_pre_handle`
 #[async_trait]
 pub trait MatcherHandler<T = (), D = (), S = (), P = (), I = ()>: Sync {
-    fn pre_handle(&self, _session: &mut Session<T, D, S, P, I>) -> Signal {
-        Signal::NotMatch
+    fn pre_handle<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        session.extensions.insert(Unconditioned);
+        Box::pin(async { Signal::NotMatch })
+    }
+    /// 事件发送者是否有权限使用该 Handler，用于 help 等场景，默认为 true
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        _session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        Box::pin(async { true })
+    }
+    /// 在全部 pre_handle 之后执行，用于检查 Handler 所需的参数，默认为 `Signal::Matched`
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        _session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        Box::pin(async { Signal::Matched })
     }
     async fn handle(&self, session: Session<T, D, S, P, I>);
 }
@@ -122,6 +322,64 @@ pub trait MatcherHandlerExt<T = (), D = (), S = (), P = (), I = ()>:
             before: false,
         }
     }
+    fn with_async_rule<R>(self, rule: R) -> LayeredAsyncRule<R, Self>
+    where
+        Self: Sized,
+        R: AsyncRule<T, D, S, P, I>,
+    {
+        LayeredAsyncRule {
+            rule,
+            handler: self,
+            before: false,
+        }
+    }
+    fn with_async_pre_handler<PR>(self, pre: PR) -> LayeredAsyncPreHandler<PR, Self>
+    where
+        Self: Sized,
+        PR: AsyncPreHandler<T, D, S, P, I>,
+    {
+        LayeredAsyncPreHandler {
+            pre,
+            handler: self,
+            before: false,
+        }
+    }
+    /// 仅允许 Rule 匹配的事件发送者使用，同时在 help 中对其他用户隐藏
+    fn with_permission<R>(self, rule: R) -> PermissionLayer<R, Self>
+    where
+        Self: Sized,
+        R: Rule<T, D, S, P, I>,
+    {
+        PermissionLayer {
+            rule,
+            handler: self,
+        }
+    }
+    /// 与 `with_permission` 相同，使用 AsyncRule 鉴权
+    fn with_async_permission<R>(self, rule: R) -> AsyncPermissionLayer<R, Self>
+    where
+        Self: Sized,
+        R: AsyncRule<T, D, S, P, I>,
+    {
+        AsyncPermissionLayer {
+            rule,
+            handler: self,
+        }
+    }
+    /// 按 `Cooldown` 限制触发频率
+    fn with_cooldown(self, cooldown: Cooldown) -> CooldownLayer<Self>
+    where
+        Self: Sized,
+    {
+        CooldownLayer::new(cooldown, self)
+    }
+    /// 按 `Concurrency` 限制同时运行的任务数
+    fn with_concurrency(self, concurrency: Concurrency) -> ConcurrencyLayer<Self>
+    where
+        Self: Sized,
+    {
+        ConcurrencyLayer::new(concurrency, self)
+    }
     fn with_extra_handler<H>(self, handler: H) -> LayeredHandler<H, Self>
     where
         Self: Sized,
@@ -141,40 +399,57 @@ pub trait MatcherHandlerExt<T = (), D = (), S = (), P = (), I = ()>:
         I: TryFromEvent<ImplLevel> + Send + Sync + 'static,
         P: TryFromEvent<PlatformLevel> + Send + Sync + 'static,
     {
-        Box::new(BoxedHandler(
+        Matcher::new(Box::new(BoxedHandler(
             Arc::new(self),
-            std::marker::PhantomData::default(),
-        ))
+            std::marker::PhantomData,
+        )))
     }
 }
 
 impl<T, D, S, P, I, H: MatcherHandler<T, D, S, P, I>> MatcherHandlerExt<T, D, S, P, I> for H {}
 
-pub struct HandlerFn<H>(H);
+pub struct HandlerFn<H, A>(H, std::marker::PhantomData<fn() -> A>);
 
-pub fn handler_fn<H, T, D, S, P, I, Fut>(inner: H) -> HandlerFn<H>
+/// 以函数构造 Handler，函数首个参数为 Session，其余参数为 FromSession 提取器
+///
+/// `handler_fn(|s: Session<Message, Group>, Mentions(users): Mentions| async move { .. })`
+pub fn handler_fn<H, A, T, D, S, P, I>(inner: H) -> HandlerFn<H, A>
 where
-    H: Fn(Session<T, D, S, P, I>) -> Fut + Send,
-    Fut: Future<Output = ()> + Send,
+    H: SessionFn<A, T, D, S, P, I>,
     T: Send + 'static,
     D: Send + 'static,
     S: Send + 'static,
     P: Send + 'static,
     I: Send + 'static,
 {
-    HandlerFn(inner)
+    HandlerFn(inner, std::marker::PhantomData)
 }
 
-impl<T, D, S, P, I, H, Fut> MatcherHandler<T, D, S, P, I> for HandlerFn<H>
+impl<T, D, S, P, I, H, A> MatcherHandler<T, D, S, P, I> for HandlerFn<H, A>
 where
-    H: Fn(Session<T, D, S, P, I>) -> Fut + Send + Sync + 'static,
-    Fut: Future<Output = ()> + Send + 'static,
+    H: SessionFn<A, T, D, S, P, I>,
     T: Sync + Send + 'static,
     D: Sync + Send + 'static,
     S: Sync + Send + 'static,
     P: Sync + Send + 'static,
     I: Sync + Send + 'static,
 {
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        let signal = if H::extract(session) {
+            Signal::Matched
+        } else {
+            Signal::NotMatch
+        };
+        Box::pin(async move { signal })
+    }
     fn handle<'a, 'b>(
         &'a self,
         session: Session<T, D, S, P, I>,
@@ -183,17 +458,18 @@ where
         'a: 'b,
         Self: 'b,
     {
-        Box::pin(self.0(session))
+        self.0.call(session)
     }
 }
 
-pub struct MayFailHandlerFn<H, M>(H, std::marker::PhantomData<M>);
+pub struct MayFailHandlerFn<H, R, M>(H, std::marker::PhantomData<fn() -> (R, M)>);
 
-pub fn may_fail_handler_fn<H, T, D, S, P, I, M>(inner: H) -> MayFailHandlerFn<H, M>
+/// 以可能失败的函数构造 Handler，Ok 时通过 IntoReply 回复，Err 时通过 Matchers 上报错误
+pub fn may_fail_handler_fn<H, T, D, S, P, I, R, M>(inner: H) -> MayFailHandlerFn<H, R, M>
 where
     H: for<'a> Fn(
             &'a Session<T, D, S, P, I>,
-        ) -> Pin<Box<dyn Future<Output = Result<(), M>> + Send + 'a>>
+        ) -> Pin<Box<dyn Future<Output = Result<R, M>> + Send + 'a>>
         + Send
         + Sync,
     T: Clone + Send + Sync + 'static,
@@ -201,18 +477,18 @@ where
     S: Clone + Send + Sync + 'static,
     P: Clone + Send + Sync + 'static,
     I: Clone + Send + Sync + 'static,
+    R: IntoReply<T, D, S, P, I> + 'static,
     M: IntoMessage + Send + Sync + 'static,
-    Session<T, D, S, P, I>: ReplyAbleSession,
 {
-    MayFailHandlerFn(inner, std::marker::PhantomData::default())
+    MayFailHandlerFn(inner, std::marker::PhantomData)
 }
 
 #[async_trait]
-impl<T, D, S, P, I, H, M> MatcherHandler<T, D, S, P, I> for MayFailHandlerFn<H, M>
+impl<T, D, S, P, I, H, R, M> MatcherHandler<T, D, S, P, I> for MayFailHandlerFn<H, R, M>
 where
     H: for<'a> Fn(
             &'a Session<T, D, S, P, I>,
-        ) -> Pin<Box<dyn Future<Output = Result<(), M>> + Send + 'a>>
+        ) -> Pin<Box<dyn Future<Output = Result<R, M>> + Send + 'a>>
         + Send
         + Sync,
     T: Clone + Send + Sync + 'static,
@@ -220,13 +496,16 @@ where
     S: Clone + Send + Sync + 'static,
     P: Clone + Send + Sync + 'static,
     I: Clone + Send + Sync + 'static,
+    R: IntoReply<T, D, S, P, I> + 'static,
     M: IntoMessage + Send + Sync + 'static,
-    Session<T, D, S, P, I>: ReplyAbleSession,
 {
     async fn handle(&self, session: Session<T, D, S, P, I>) {
-        if let Err(e) = self.0(&session).await {
-            session.send("Matcher Error:").await.ok();
-            session.send(e.into_message()).await.ok();
+        match self.0(&session).await {
+            Ok(r) => r.reply(&session).await,
+            Err(e) => {
+                let error = e.into_message().iter().map(|seg| seg.alt()).collect();
+                session.report(MatcherError::Failed(error)).await
+            }
         }
     }
 }
@@ -257,9 +536,39 @@ where
     H: MatcherHandler<C> + Send + Sync,
     C: Clone + Send + Sync + 'static,
 {
-    fn pre_handle(&self, session: &mut Session<C>) -> Signal {
+    fn pre_handle<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<C>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
         self.handler.pre_handle(session)
     }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<C>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<C>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
     fn handle<'a, 't>(
         &'a self,
         session: Session<C>,
@@ -275,11 +584,11 @@ where
     }
 }
 
-impl<C, I, Fut> ExtraHandler<C> for HandlerFn<I>
+/// 作为 ExtraHandler 时，参数提取失败将跳过执行
+impl<C, H, A> ExtraHandler<C> for HandlerFn<H, A>
 where
     C: Sync + Send + 'static,
-    I: Fn(Session<C>) -> Fut + Send + Sync + 'static,
-    Fut: Future<Output = ()> + Send + 'static,
+    H: SessionFn<A, C>,
 {
     fn handle<'a, 'b>(
         &'a self,
@@ -289,6 +598,108 @@ where
         'a: 'b,
         Self: 'b,
     {
-        Box::pin(self.0(session))
+        self.0.call(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, session, DummyCaller};
+    use crate::{async_pre_handle_fn, async_rule_fn, handler_fn, rule_fn, FromSession};
+    use walle_core::event::{Message, Private};
+
+    type PrivateSession = Session<Message, Private>;
+
+    fn handler() -> impl MatcherHandler<Message, Private> {
+        handler_fn(|_: PrivateSession| async {})
+    }
+
+    /// 消息为 `text` 时匹配的异步 Rule
+    fn text_is(text: &'static str) -> impl AsyncRule<Message, Private> {
+        async_rule_fn(move |session: &PrivateSession| {
+            let signal = if session.event.ty.alt_message == text {
+                Signal::Matched
+            } else {
+                Signal::NotMatch
+            };
+            Box::pin(async move { signal })
+        })
+    }
+
+    fn new_session(text: &str) -> PrivateSession {
+        session(message_event("1", "u", None, text))
+    }
+
+    #[tokio::test]
+    async fn bare_handler_does_not_match() {
+        let mut session = new_session("hi");
+        assert_eq!(handler().pre_handle(&mut session).await, Signal::NotMatch);
+    }
+
+    #[tokio::test]
+    async fn async_layers_join_with_and() {
+        let handler = text_is("hi").layer(
+            async_pre_handle_fn(|session: &mut PrivateSession| {
+                session.extensions.insert(1u8);
+                Box::pin(async { Signal::MatchAndBlock })
+            })
+            .layer(handler()),
+        );
+        let mut session = new_session("hi");
+        assert_eq!(
+            handler.pre_handle(&mut session).await,
+            Signal::MatchAndBlock
+        );
+        assert_eq!(session.extensions.get::<u8>(), Some(&1));
+
+        let mut session = new_session("hello");
+        assert_eq!(handler.pre_handle(&mut session).await, Signal::NotMatch);
+        assert!(session.extensions.get::<u8>().is_none());
+    }
+
+    #[tokio::test]
+    async fn inner_layer_can_reject() {
+        let handler =
+            rule_fn(|_: &PrivateSession| Signal::Matched).layer(text_is("hi").layer(handler()));
+        let mut session = new_session("hello");
+        assert_eq!(handler.pre_handle(&mut session).await, Signal::NotMatch);
+        let mut session = new_session("hi");
+        assert_eq!(handler.pre_handle(&mut session).await, Signal::Matched);
+    }
+
+    static EXTRACTED: AtomicUsize = AtomicUsize::new(0);
+
+    /// 记录提取次数的提取器
+    struct Counted;
+
+    impl FromSession<Message, Private> for Counted {
+        fn from_session(_: &PrivateSession) -> Option<Self> {
+            EXTRACTED.fetch_add(1, Ordering::SeqCst);
+            Some(Self)
+        }
+    }
+
+    #[tokio::test]
+    async fn extractors_run_once() {
+        let handle = MatchersHandle::default();
+        handle
+            .insert(
+                rule_fn(|_: &PrivateSession| Signal::Matched)
+                    .layer(handler_fn(|_: PrivateSession, _: Counted| async { "ok" }))
+                    .boxed(),
+            )
+            .await;
+        let (dummy, caller) = DummyCaller::arc();
+        handle
+            .call(
+                &message_event("1", "u", None, "hi"),
+                &Arc::new(MatchersConfig::default()),
+                &caller,
+            )
+            .await;
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(EXTRACTED.load(Ordering::SeqCst), 1);
+        assert_eq!(dummy.sent_texts(), ["ok"]);
     }
 }
@@ -1,60 +1,584 @@
+use super::normalize::normalize_event;
+use super::report::reply_event;
+use super::suggest::{suggest, unknown_command};
+use super::switch::write_file;
 use super::RawMatcherHandler;
-use crate::{ActionCaller, Signal};
-use crate::{MatchersConfig, MatchersHook};
+use crate::{ActionCaller, MatcherSwitches, Signal, SwitchScope};
+use crate::{ErrorReport, ErrorReporter, TracingReporter};
+use crate::{MatchersConfig, MatchersHook, PendingWait, Role};
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::sync::Arc;
+use dashmap::DashMap;
+use futures_util::future::join_all;
+use std::collections::BTreeMap;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
 use tokio::{
     sync::{Mutex, RwLock},
     task::JoinHandle,
 };
-use tracing::info;
+use tracing::{info, warn};
 use walle_core::prelude::WalleError;
+use walle_core::util::ValueMapExt;
 use walle_core::{
     action::Action, error::WalleResult, event::Event, resp::Resp, ActionHandler, EventHandler,
     OneBot,
 };
 
-pub type Matcher = Box<dyn RawMatcherHandler + Send + Sync + 'static>;
-pub type TempMatchers = Arc<Mutex<HashMap<String, Matcher>>>;
+/// Matcher 默认优先级
+pub const DEFAULT_PRIORITY: i32 = 1;
 
-#[derive(Default)]
-pub struct Matchers {
-    pub inner: Vec<Matcher>,
-    pub config: RwLock<Arc<MatchersConfig>>,
-    temps: TempMatchers,
-    hooks: Vec<Box<dyn MatchersHook + Send + 'static>>,
-    ob: RwLock<Option<Arc<dyn ActionCaller + Send + 'static>>>,
+/// 临时 Matcher 优先级，该层总是阻止更低优先级的 Matcher，仅最早注册的匹配者执行，且不会出现在帮助中
+pub const TEMP_PRIORITY: i32 = i32::MIN;
+
+/// Matcher 元数据，用于生成帮助信息
+#[derive(Debug, Clone, Default)]
+pub struct MatcherMetadata {
+    /// 命令名，未命名的 Matcher 不会出现在帮助中
+    pub name: Option<String>,
+    pub description: String,
+    pub usage: String,
+    pub examples: Vec<String>,
+    /// 命令名及别名，用于帮助与未知命令提示
+    pub commands: Vec<String>,
 }
 
-impl Matchers {
-    pub fn add_matcher(mut self, matcher: Matcher) -> Self {
-        self.inner.push(matcher);
+/// 已装箱的 Matcher
+pub struct Matcher {
+    /// 优先级，数值越小越先执行
+    pub priority: i32,
+    pub metadata: MatcherMetadata,
+    /// 存活时间，自注册时开始计算
+    pub ttl: Option<Duration>,
+    pub handler: Box<dyn RawMatcherHandler + Send + Sync + 'static>,
+    expire_at: Option<Instant>,
+    remaining: Option<AtomicUsize>,
+}
+
+impl Matcher {
+    pub fn new(handler: Box<dyn RawMatcherHandler + Send + Sync + 'static>) -> Self {
+        Self {
+            priority: DEFAULT_PRIORITY,
+            metadata: MatcherMetadata::default(),
+            ttl: None,
+            handler,
+            expire_at: None,
+            remaining: None,
+        }
+    }
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+    pub fn name(mut self, name: &str) -> Self {
+        self.metadata.name = Some(name.to_string());
+        self
+    }
+    pub fn description(mut self, description: &str) -> Self {
+        self.metadata.description = description.to_string();
+        self
+    }
+    pub fn usage(mut self, usage: &str) -> Self {
+        self.metadata.usage = usage.to_string();
+        self
+    }
+    pub fn example(mut self, example: &str) -> Self {
+        self.metadata.examples.push(example.to_string());
         self
     }
-    async fn temp_call(
+    /// 声明 Matcher 响应的命令名，可重复
+    pub fn command(mut self, command: &str) -> Self {
+        self.metadata.commands.push(command.to_string());
+        self
+    }
+    /// 超过存活时间后移除
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+    /// 匹配指定次数后移除
+    pub fn times(mut self, times: usize) -> Self {
+        self.remaining = Some(AtomicUsize::new(times));
+        self
+    }
+    /// 未过期且剩余次数未耗尽
+    fn is_alive(&self, now: Instant) -> bool {
+        self.expire_at.is_none_or(|expire_at| now < expire_at)
+            && self
+                .remaining
+                .as_ref()
+                .is_none_or(|remaining| remaining.load(Ordering::Acquire) > 0)
+    }
+    /// 使用次数已耗尽，次数在 Handler 执行前由 `RawMatcherHandler::call` 预留
+    fn is_exhausted(&self) -> bool {
+        self.remaining
+            .as_ref()
+            .is_some_and(|remaining| remaining.load(Ordering::Acquire) == 0)
+    }
+    /// 未命名的 Matcher 无法被禁用
+    fn is_enabled(&self, switches: &MatcherSwitches, scopes: &[SwitchScope]) -> bool {
+        match &self.metadata.name {
+            Some(name) => switches.is_enabled(name, scopes),
+            None => true,
+        }
+    }
+}
+
+/// 同一优先级的 Matcher，同层 Matcher 并发执行
+#[derive(Default)]
+pub struct MatchersLevel {
+    /// 本层任一 Matcher 匹配后不再执行更低优先级的 Matcher
+    pub block: bool,
+    /// 本层按注册顺序依次执行，仅最先匹配的 Matcher 执行
+    pub first_match: bool,
+    pub matchers: Vec<Arc<Matcher>>,
+}
+
+impl MatchersLevel {
+    fn new(priority: i32) -> Self {
+        Self {
+            block: priority == TEMP_PRIORITY,
+            // 同一用户的多个等待中仅最早的一个收到回复
+            first_match: priority == TEMP_PRIORITY,
+            matchers: vec![],
+        }
+    }
+    /// 复制本层存活且已启用的 Matcher，使调用时无需持有锁
+    fn snapshot(
+        &self,
+        now: Instant,
+        switches: &MatcherSwitches,
+        scopes: &[SwitchScope],
+    ) -> LevelSnapshot {
+        let mut sweep = false;
+        let matchers = self
+            .matchers
+            .iter()
+            .filter(|matcher| {
+                let alive = matcher.is_alive(now);
+                sweep |= !alive;
+                alive
+            })
+            .filter(|matcher| matcher.is_enabled(switches, scopes))
+            .cloned()
+            .collect();
+        LevelSnapshot {
+            block: self.block,
+            first_match: self.first_match,
+            matchers,
+            sweep,
+        }
+    }
+}
+
+/// 某一层 Matcher 的快照
+struct LevelSnapshot {
+    block: bool,
+    first_match: bool,
+    matchers: Vec<Arc<Matcher>>,
+    /// 快照时已存在需要移除的 Matcher
+    sweep: bool,
+}
+
+impl LevelSnapshot {
+    /// 返回是否阻止更低优先级的 Matcher、是否存在需要移除的 Matcher，以及是否有 Matcher 匹配
+    async fn call(
         &self,
         event: &Event,
         config: &Arc<MatchersConfig>,
         ob: &Arc<dyn ActionCaller + Send + 'static>,
-    ) -> bool {
-        let mut matched_temp_key: Option<String> = None;
-        let mut temps = self.temps.lock().await;
-        for temp in temps.iter() {
-            if temp.1.call(event.clone(), &config, ob, &self.temps).await != Signal::NotMatch {
-                matched_temp_key = Some(temp.0.to_owned());
+        handle: &MatchersHandle,
+    ) -> (bool, bool, bool) {
+        let mut sweep = self.sweep;
+        let calls = self.matchers.iter().map(|matcher| {
+            matcher.handler.call(
+                event.clone(),
+                config,
+                ob,
+                handle,
+                matcher.metadata.name.as_deref(),
+                matcher.remaining.as_ref(),
+            )
+        });
+        let signals = if self.first_match {
+            let mut signals = Vec::with_capacity(self.matchers.len());
+            for call in calls {
+                let signal = call.await;
+                signals.push(signal);
+                if signal.is_matched() {
+                    break;
+                }
+            }
+            signals
+        } else {
+            join_all(calls).await
+        };
+        let mut block = false;
+        let mut matched = false;
+        for (matcher, signal) in self.matchers.iter().zip(signals) {
+            if signal.is_matched() {
+                sweep |= matcher.is_exhausted();
+                matched = true;
+            }
+            block |= match signal {
+                Signal::MatchAndBlock => true,
+                Signal::Matched => self.block,
+                Signal::NotMatch => false,
+            };
+        }
+        (block, sweep, matched)
+    }
+}
+
+fn insert_matcher(
+    levels: &mut BTreeMap<i32, MatchersLevel>,
+    mut matcher: Matcher,
+) -> Option<Arc<Matcher>> {
+    matcher.expire_at = matcher.ttl.map(|ttl| Instant::now() + ttl);
+    let replaced = match &matcher.metadata.name {
+        Some(name) => remove_matcher(levels, name),
+        None => None,
+    };
+    let priority = matcher.priority;
+    levels
+        .entry(priority)
+        .or_insert_with(|| MatchersLevel::new(priority))
+        .matchers
+        .push(Arc::new(matcher));
+    replaced
+}
+
+fn remove_matcher(levels: &mut BTreeMap<i32, MatchersLevel>, name: &str) -> Option<Arc<Matcher>> {
+    levels.values_mut().find_map(|level| {
+        level
+            .matchers
+            .iter()
+            .position(|matcher| matcher.metadata.name.as_deref() == Some(name))
+            .map(|index| level.matchers.remove(index))
+    })
+}
+
+/// 可在 Session 中访问的 Matchers 句柄
+#[derive(Clone, Default)]
+pub struct MatchersHandle {
+    levels: Arc<RwLock<BTreeMap<i32, MatchersLevel>>>,
+    switches: Arc<RwLock<MatcherSwitches>>,
+    /// 串行写入开关文件
+    persisting: Arc<Mutex<()>>,
+    reporters: Arc<RwLock<Vec<Box<dyn ErrorReporter + Send + Sync + 'static>>>>,
+    /// 各会话上次命令提示的时间
+    suggested: Arc<DashMap<String, Instant>>,
+    /// 群成员角色缓存
+    roles: Arc<DashMap<String, (Role, Instant)>>,
+    /// 上次清理角色缓存的时间
+    roles_swept: Arc<std::sync::Mutex<Option<Instant>>>,
+    /// 等待中的临时 Matcher，键为临时 Matcher 名
+    pending: Arc<DashMap<String, PendingWait>>,
+}
+
+impl MatchersHandle {
+    /// 运行时注册 Matcher，已存在同名 Matcher 时将其替换并返回
+    pub async fn insert(&self, matcher: Matcher) -> Option<Arc<Matcher>> {
+        insert_matcher(&mut *self.levels.write().await, matcher)
+    }
+    /// 运行时移除同名 Matcher
+    pub async fn remove(&self, name: &str) -> Option<Arc<Matcher>> {
+        remove_matcher(&mut *self.levels.write().await, name)
+    }
+    /// 上报 Handler 错误，未添加 ErrorReporter 时使用 TracingReporter，
+    /// 并向用户回复 `MatchersConfig::error_message`
+    pub async fn report(
+        &self,
+        report: ErrorReport,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        config: &Arc<MatchersConfig>,
+    ) {
+        let reporters = self.reporters.read().await;
+        if reporters.is_empty() {
+            TracingReporter.report(&report, caller, config).await;
+        }
+        for reporter in reporters.iter() {
+            reporter.report(&report, caller, config).await;
+        }
+        if !config.error_message.is_empty() {
+            if let Err(e) = reply_event(caller, &report.event, &config.error_message).await {
+                warn!(target: "Walle", "reply error message failed: {}", e);
+            }
+        }
+    }
+    /// 未超过缓存时间的群成员角色
+    pub(crate) fn cached_role(&self, key: &str, ttl: Duration) -> Option<Role> {
+        self.roles
+            .get(key)
+            .filter(|entry| entry.1.elapsed() < ttl)
+            .map(|entry| entry.0)
+    }
+    /// 缓存群成员角色，每隔 `ttl` 移除已过期的缓存
+    pub(crate) fn cache_role(&self, key: String, role: Role, ttl: Duration) {
+        let now = Instant::now();
+        self.roles.insert(key, (role, now));
+        {
+            let mut swept = self.roles_swept.lock().unwrap();
+            match *swept {
+                Some(last) if now.duration_since(last) < ttl => return,
+                _ => *swept = Some(now),
+            }
+        }
+        self.roles
+            .retain(|_, entry| now.duration_since(entry.1) < ttl);
+    }
+    /// 用户所有等待中的临时 Matcher
+    pub fn pending_waits(&self, user_id: &str) -> Vec<PendingWait> {
+        self.pending
+            .iter()
+            .filter(|wait| wait.user_id == user_id)
+            .map(|wait| wait.value().clone())
+            .collect()
+    }
+    /// 取消等待，对应的 `ReplyAbleSession::get` 立即返回错误
+    pub async fn cancel_wait(&self, id: &str) -> bool {
+        let cancelled = self.remove_pending(id);
+        self.remove(id).await.is_some() || cancelled
+    }
+    /// 取消用户所有等待，返回取消的数量
+    pub async fn cancel_waits(&self, user_id: &str) -> usize {
+        let mut count = 0;
+        for wait in self.pending_waits(user_id) {
+            if self.cancel_wait(&wait.id).await {
+                count += 1;
+            }
+        }
+        count
+    }
+    pub(crate) fn add_pending(&self, wait: PendingWait) {
+        self.pending.insert(wait.id.clone(), wait);
+    }
+    pub(crate) fn remove_pending(&self, id: &str) -> bool {
+        self.pending.remove(id).is_some()
+    }
+    /// 移除已过期或次数耗尽的 Matcher
+    async fn sweep(&self) {
+        let now = Instant::now();
+        for level in self.levels.write().await.values_mut() {
+            level.matchers.retain(|matcher| matcher.is_alive(now));
+        }
+    }
+    fn named(levels: &BTreeMap<i32, MatchersLevel>) -> impl Iterator<Item = &Arc<Matcher>> {
+        let now = Instant::now();
+        levels
+            .iter()
+            .filter(|(priority, _)| **priority != TEMP_PRIORITY)
+            .flat_map(|(_, level)| level.matchers.iter())
+            .filter(move |matcher| matcher.metadata.name.is_some() && matcher.is_alive(now))
+    }
+    /// 按优先级顺序返回所有已命名 Matcher 的元数据
+    pub async fn metadatas(&self) -> Vec<MatcherMetadata> {
+        Self::named(&*self.levels.read().await)
+            .map(|matcher| matcher.metadata.clone())
+            .collect()
+    }
+    /// 按优先级顺序返回该事件中已启用且发送者有权使用的已命名 Matcher 的元数据
+    pub async fn permitted_metadatas(
+        &self,
+        event: &Event,
+        config: &Arc<MatchersConfig>,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+    ) -> Vec<MatcherMetadata> {
+        let scopes = SwitchScope::from_event(event);
+        let matchers: Vec<Arc<Matcher>> = {
+            let switches = self.switches.read().await;
+            let levels = self.levels.read().await;
+            Self::named(&levels)
+                .filter(|matcher| matcher.is_enabled(&switches, &scopes))
+                .cloned()
+                .collect()
+        };
+        let permits = join_all(
+            matchers
+                .iter()
+                .map(|matcher| matcher.handler.permit(event.clone(), config, caller, self)),
+        )
+        .await;
+        matchers
+            .into_iter()
+            .zip(permits)
+            .filter(|(_, permit)| *permit)
+            .map(|(matcher, _)| matcher.metadata.clone())
+            .collect()
+    }
+    /// 是否存在该名称的 Matcher
+    pub async fn contains(&self, name: &str) -> bool {
+        Self::named(&*self.levels.read().await)
+            .any(|matcher| matcher.metadata.name.as_deref() == Some(name))
+    }
+    /// 在作用域中启用 Matcher 并写入 `MatchersConfig::switch_file`，状态发生变化时返回 true
+    pub async fn enable(&self, name: &str, scope: &SwitchScope) -> WalleResult<bool> {
+        self.update_switches(|switches| switches.enable(name, scope))
+            .await
+    }
+    /// 在作用域中禁用 Matcher 并写入 `MatchersConfig::switch_file`，状态发生变化时返回 true
+    pub async fn disable(&self, name: &str, scope: &SwitchScope) -> WalleResult<bool> {
+        self.update_switches(|switches| switches.disable(name, scope))
+            .await
+    }
+    /// 修改开关并在释放开关锁后写入文件，写入按修改顺序串行执行，不阻塞事件分发
+    async fn update_switches(
+        &self,
+        update: impl FnOnce(&mut MatcherSwitches) -> bool,
+    ) -> WalleResult<bool> {
+        let _persisting = self.persisting.lock().await;
+        let data = {
+            let mut switches = self.switches.write().await;
+            if !update(&mut switches) {
+                return Ok(false);
+            }
+            switches.persist_data()?
+        };
+        if let Some((path, data)) = data {
+            write_file(&path, &data).await?;
+        }
+        Ok(true)
+    }
+    /// Matcher 在该事件的作用域中是否启用
+    pub async fn is_enabled(&self, name: &str, event: &Event) -> bool {
+        self.switches
+            .read()
+            .await
+            .is_enabled(name, &SwitchScope::from_event(event))
+    }
+    pub(crate) async fn call(
+        &self,
+        event: &Event,
+        config: &Arc<MatchersConfig>,
+        ob: &Arc<dyn ActionCaller + Send + 'static>,
+    ) {
+        let scopes = SwitchScope::from_event(event);
+        let snapshots: Vec<LevelSnapshot> = {
+            let now = Instant::now();
+            let switches = self.switches.read().await;
+            let levels = self.levels.read().await;
+            levels
+                .values()
+                .map(|level| level.snapshot(now, &switches, &scopes))
+                .collect()
+        };
+        let mut need_sweep = false;
+        let mut any_matched = false;
+        for level in snapshots.iter() {
+            let (block, sweep, matched) = level.call(event, config, ob, self).await;
+            need_sweep |= sweep;
+            any_matched |= matched;
+            if block {
                 break;
             }
         }
-        if let Some(key) = matched_temp_key {
-            temps.remove(&key);
-            true
-        } else {
-            false
+        if need_sweep {
+            self.sweep().await;
+        }
+        if !any_matched && config.command_suggestion {
+            self.suggest_command(event, config, ob, &scopes).await;
+        }
+    }
+    /// 未知命令时回复最接近的命令，同一会话按 `suggestion_interval` 限流
+    async fn suggest_command(
+        &self,
+        event: &Event,
+        config: &Arc<MatchersConfig>,
+        ob: &Arc<dyn ActionCaller + Send + 'static>,
+        scopes: &[SwitchScope],
+    ) {
+        if event.ty != "message" {
+            return;
+        }
+        let Ok(alt_message) = event.extra.get_downcast::<String>("alt_message") else {
+            return;
+        };
+        let Some((start, word)) = unknown_command(&alt_message, config) else {
+            return;
+        };
+        let Some(key) = scopes.last().map(SwitchScope::key) else {
+            return;
+        };
+        let interval = Duration::from_secs(config.suggestion_interval);
+        if self
+            .suggested
+            .get(&key)
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+        let metadatas = self.permitted_metadatas(event, config, ob).await;
+        let commands = suggest(word, metadatas.iter().flat_map(|m| m.commands.iter()));
+        if commands.is_empty() {
+            return;
+        }
+        self.suggested.insert(key, Instant::now());
+        let commands: Vec<String> = commands
+            .into_iter()
+            .map(|command| format!("{}{}", start, command))
+            .collect();
+        let reply = format!(
+            "未知命令 {}{}，你是不是想找：{}",
+            start,
+            word,
+            commands.join("、")
+        );
+        if let Err(e) = reply_event(ob, event, &reply).await {
+            warn!(target: "Walle", "reply command suggestion failed: {}", e);
         }
     }
 }
 
+#[derive(Default)]
+pub struct Matchers {
+    handle: MatchersHandle,
+    pub config: RwLock<Arc<MatchersConfig>>,
+    hooks: Vec<Box<dyn MatchersHook + Send + 'static>>,
+    ob: RwLock<Option<Arc<dyn ActionCaller + Send + 'static>>>,
+}
+
+impl Matchers {
+    pub fn add_matcher(self, matcher: Matcher) -> Self {
+        insert_matcher(
+            &mut self
+                .handle
+                .levels
+                .try_write()
+                .expect("matchers locked while building"),
+            matcher,
+        );
+        self
+    }
+    /// 设置该优先级任一 Matcher 匹配后是否阻止更低优先级的 Matcher
+    pub fn block_level(self, priority: i32, block: bool) -> Self {
+        self.handle
+            .levels
+            .try_write()
+            .expect("matchers locked while building")
+            .entry(priority)
+            .or_insert_with(|| MatchersLevel::new(priority))
+            .block = block;
+        self
+    }
+    /// 添加 Handler 错误上报 Hook
+    pub fn add_reporter<R>(self, reporter: R) -> Self
+    where
+        R: ErrorReporter + Send + Sync + 'static,
+    {
+        self.handle
+            .reporters
+            .try_write()
+            .expect("matchers locked while building")
+            .push(Box::new(reporter));
+        self
+    }
+    pub fn handle(&self) -> MatchersHandle {
+        self.handle.clone()
+    }
+}
+
 #[async_trait]
 impl EventHandler<Event, Action, Resp> for Matchers {
     type Config = MatchersConfig;
@@ -68,6 +592,12 @@ impl EventHandler<Event, Action, Resp> for Matchers {
         EH: EventHandler<Event, Action, Resp> + Send + Sync + 'static,
     {
         *self.ob.write().await = Some(Arc::new(ob.clone()));
+        if !config.switch_file.is_empty() {
+            match MatcherSwitches::load(&config.switch_file).await {
+                Ok(switches) => *self.handle.switches.write().await = switches,
+                Err(e) => warn!(target: "Walle", "load matcher switches failed: {}", e),
+            }
+        }
         *self.config.write().await = Arc::new(config);
         let ob = self.ob.read().await.clone().unwrap();
         for hook in self.hooks.iter() {
@@ -75,7 +605,7 @@ impl EventHandler<Event, Action, Resp> for Matchers {
         }
         Ok(vec![])
     }
-    async fn call<AH, EH>(&self, event: Event, _: &Arc<OneBot<AH, EH>>) -> WalleResult<()>
+    async fn call<AH, EH>(&self, mut event: Event, _: &Arc<OneBot<AH, EH>>) -> WalleResult<()>
     where
         AH: ActionHandler<Event, Action, Resp> + Send + Sync + 'static,
         EH: EventHandler<Event, Action, Resp> + Send + Sync + 'static,
@@ -87,15 +617,10 @@ impl EventHandler<Event, Action, Resp> for Matchers {
         let ob: Arc<dyn ActionCaller + Send + 'static> =
             self.ob.read().await.clone().ok_or(WalleError::NotStarted)?;
         let config = self.config.read().await.clone();
-        if self.temp_call(&event, &config, &ob).await {
-            return Ok(());
-        }
-        for matcher in &self.inner {
-            if matcher.call(event.clone(), &config, &ob, &self.temps).await == Signal::MatchAndBlock
-            {
-                return Ok(());
-            }
+        if config.normalize {
+            normalize_event(&mut event);
         }
+        self.handle.call(&event, &config, &ob).await;
         Ok(())
     }
     async fn shutdown(&self) {
@@ -106,3 +631,199 @@ impl EventHandler<Event, Action, Resp> for Matchers {
         *self.ob.write().await = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::start_with;
+    use crate::testing::{message_event, DummyCaller};
+    use crate::{handler_fn, MatcherHandlerExt, Rule, Session};
+    use walle_core::event::{Message, Private};
+
+    fn counting_matcher(count: Arc<AtomicUsize>) -> Matcher {
+        start_with("hi")
+            .layer(handler_fn(move |_: Session<Message, Private>| {
+                count.fetch_add(1, Ordering::SeqCst);
+                async {}
+            }))
+            .boxed()
+    }
+
+    #[tokio::test]
+    async fn times_runs_once_under_concurrent_events() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let handle = MatchersHandle::default();
+        handle
+            .insert(counting_matcher(count.clone()).name("once").times(1))
+            .await;
+        let (_, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        let events: Vec<Event> = (0..8)
+            .map(|i| message_event(&i.to_string(), "u", None, "hi"))
+            .collect();
+        join_all(
+            events
+                .iter()
+                .map(|event| handle.call(event, &config, &caller)),
+        )
+        .await;
+        tokio::task::yield_now().await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!handle.contains("once").await);
+    }
+
+    #[tokio::test]
+    async fn expired_matcher_is_skipped() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let handle = MatchersHandle::default();
+        handle
+            .insert(
+                counting_matcher(count.clone())
+                    .name("ttl")
+                    .ttl(Duration::from_millis(10)),
+            )
+            .await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let (_, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        handle
+            .call(&message_event("1", "u", None, "hi"), &config, &caller)
+            .await;
+        tokio::task::yield_now().await;
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn expired_roles_are_swept() {
+        let handle = MatchersHandle::default();
+        let ttl = Duration::from_millis(10);
+        handle.cache_role("a".to_string(), Role::Admin, ttl);
+        assert_eq!(handle.cached_role("a", ttl), Some(Role::Admin));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(handle.cached_role("a", ttl), None);
+        handle.cache_role("b".to_string(), Role::Owner, ttl);
+        assert!(!handle.roles.contains_key("a"));
+        assert!(handle.roles.contains_key("b"));
+    }
+
+    #[tokio::test]
+    async fn switches_are_written_after_update() {
+        assert!(MatchersConfig::default().switch_file.is_empty());
+        let path = std::env::temp_dir().join(format!("walle_switches_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let handle = MatchersHandle::default();
+        *handle.switches.write().await = MatcherSwitches::load(path).await.unwrap();
+        let scope = SwitchScope::Group("g".to_string());
+        assert!(handle.disable("echo", &scope).await.unwrap());
+        assert!(!handle.disable("echo", &scope).await.unwrap());
+        let loaded = MatcherSwitches::load(path).await.unwrap();
+        assert!(!loaded.is_enabled("echo", std::slice::from_ref(&scope)));
+        assert!(handle.enable("echo", &scope).await.unwrap());
+        let loaded = MatcherSwitches::load(path).await.unwrap();
+        assert!(loaded.is_enabled("echo", &[scope]));
+        tokio::fs::remove_file(path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn oldest_wait_receives_reply() {
+        use crate::builtin::{on_command, plain_text};
+        use crate::ReplyAbleSession;
+        use walle_core::event::MessageDeatilTypes;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let handle = MatchersHandle::default();
+        handle
+            .insert(
+                on_command(
+                    "ask",
+                    handler_fn(move |s: Session<Message, MessageDeatilTypes>| {
+                        let tx = tx.clone();
+                        // 同一会话中同时等待两次回复
+                        let wait = move |n: usize, mut s: Session<Message, MessageDeatilTypes>| {
+                            let tx = tx.clone();
+                            async move {
+                                if s.get("?", Some(Duration::from_secs(5))).await.is_ok() {
+                                    tx.send((n, plain_text(&s))).ok();
+                                }
+                            }
+                        };
+                        let (first, second) = (wait(0, s.clone()), wait(1, s));
+                        async move {
+                            futures_util::future::join(first, second).await;
+                        }
+                    }),
+                )
+                .boxed(),
+            )
+            .await;
+        let (_, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        let call = |id: &str, group_id: Option<&str>, text: &str| {
+            let event = message_event(id, "u", group_id, text);
+            let (handle, config, caller) = (handle.clone(), config.clone(), caller.clone());
+            async move { handle.call(&event, &config, &caller).await }
+        };
+        call("0", None, "ask").await;
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while handle.pending_waits("u").len() < 2 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        // 其他会话中的消息不会被等待接收
+        call("1", Some("g"), "c").await;
+        call("2", None, "a").await;
+        let timeout = Duration::from_secs(1);
+        let answer = tokio::time::timeout(timeout, rx.recv()).await.unwrap();
+        assert_eq!(answer, Some((0, "a".to_string())));
+        call("3", None, "b").await;
+        let answer = tokio::time::timeout(timeout, rx.recv()).await.unwrap();
+        assert_eq!(answer, Some((1, "b".to_string())));
+    }
+
+    /// 禁言成员
+    ///
+    /// 仅管理员可用
+    #[crate::matcher(
+        command = "mute",
+        alias = "禁言",
+        priority = 5,
+        usage = "mute @用户 [-t 时长]",
+        example = "mute @a -t 5m"
+    )]
+    async fn mute(_: Session<Message, walle_core::event::Group>) -> &'static str {
+        "ok"
+    }
+
+    #[crate::matcher(name = "pong")]
+    async fn ping(_: Session<Message, Private>) {}
+
+    #[tokio::test]
+    async fn matcher_macro_metadata() {
+        let matcher = mute();
+        assert_eq!(matcher.priority, 5);
+        assert_eq!(matcher.metadata.name.as_deref(), Some("mute"));
+        assert_eq!(matcher.metadata.description, "禁言成员\n\n仅管理员可用");
+        assert_eq!(matcher.metadata.usage, "mute @用户 [-t 时长]");
+        assert_eq!(matcher.metadata.examples, ["mute @a -t 5m"]);
+        assert_eq!(matcher.metadata.commands, ["mute", "禁言"]);
+
+        let matcher = ping();
+        assert_eq!(matcher.priority, DEFAULT_PRIORITY);
+        assert_eq!(matcher.metadata.name.as_deref(), Some("pong"));
+        assert!(matcher.metadata.description.is_empty());
+
+        let handle = MatchersHandle::default();
+        handle.insert(mute()).await;
+        let (dummy, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        for (id, text) in [("1", "禁言"), ("2", "mutex")] {
+            handle
+                .call(&message_event(id, "u", Some("g"), text), &config, &caller)
+                .await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(dummy.sent_texts(), ["ok"]);
+    }
+}
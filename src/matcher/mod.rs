@@ -1,33 +1,38 @@
-use walle_core::{event::BaseEvent, prelude::async_trait};
-
+mod combine;
+mod concurrency;
+mod conversation;
+mod cooldown;
+mod extract;
 mod handle;
 mod hook;
 mod matchers;
+mod normalize;
+mod permission;
 mod pre_handle;
+mod reply;
+mod report;
 mod rule;
 mod session;
+mod suggest;
+mod switch;
+mod temp;
+#[cfg(test)]
+pub(crate) mod testing;
 
+pub use combine::*;
+pub use concurrency::*;
+pub use conversation::*;
+pub use cooldown::*;
+pub use extract::*;
 pub use handle::*;
 pub use hook::*;
 pub use matchers::*;
+pub use normalize::normalize_text;
+pub use permission::*;
 pub use pre_handle::*;
+pub use reply::*;
+pub use report::*;
 pub use rule::*;
 pub use session::*;
-
-struct TempMatcher<T, D, S, P, I> {
-    pub tx: tokio::sync::mpsc::UnboundedSender<BaseEvent<T, D, S, P, I>>,
-}
-
-#[async_trait]
-impl<T, D, S, P, I> MatcherHandler<T, D, S, P, I> for TempMatcher<T, D, S, P, I>
-where
-    T: Send + 'static,
-    D: Send + 'static,
-    S: Send + 'static,
-    P: Send + 'static,
-    I: Send + 'static,
-{
-    async fn handle(&self, session: Session<T, D, S, P, I>) {
-        self.tx.send(session.event).ok();
-    }
-}
+pub use switch::*;
+pub use temp::{PendingWait, WaitError};
@@ -0,0 +1,122 @@
+use unicode_normalization::UnicodeNormalization;
+use walle_core::{event::Event, segment::Segments, util::ValueMapExt};
+
+/// 零宽字符
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}'
+    )
+}
+
+/// 全角字符转为半角
+fn to_half_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        c => c,
+    }
+}
+
+/// 规范化文本：NFKC、全角转半角、去除零宽字符，并将连续空白（换行除外）合并为一个空格
+pub fn normalize_text(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text
+        .chars()
+        .filter(|c| !is_zero_width(*c))
+        .map(to_half_width)
+        .nfkc()
+    {
+        if c.is_whitespace() && c != '\n' {
+            if !in_space {
+                normalized.push(' ');
+            }
+            in_space = true;
+        } else {
+            normalized.push(c);
+            in_space = false;
+        }
+    }
+    normalized
+}
+
+/// 规范化原始消息事件中的文本段，并重新生成 `alt_message`
+pub(crate) fn normalize_event(event: &mut Event) {
+    if event.ty != "message" {
+        return;
+    }
+    let Some(segments) = event
+        .extra
+        .get_mut("message")
+        .and_then(|message| message.as_list_mut())
+    else {
+        return;
+    };
+    for seg in segments.iter_mut().filter_map(|seg| seg.as_map_mut()) {
+        if seg.get("type").and_then(|ty| ty.as_str()) != Some("text") {
+            continue;
+        }
+        if let Some(text) = seg
+            .get_mut("data")
+            .and_then(|data| data.as_map_mut())
+            .and_then(|data| data.get_mut("text"))
+            .and_then(|text| text.as_str_mut())
+        {
+            *text = normalize_text(text);
+        }
+    }
+    if let Ok(segments) = event.extra.get_downcast::<Segments>("message") {
+        let alt: String = segments.iter().map(|seg| seg.alt()).collect();
+        event.extra.insert("alt_message".to_string(), alt.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use walle_core::value;
+
+    #[test]
+    fn normalize_width_and_spaces() {
+        assert_eq!(normalize_text("ｅｃｈｏ　ｈｉ！"), "echo hi!");
+        assert_eq!(normalize_text("a\u{200B}b \t c\n\nd"), "ab c\n\nd");
+        assert_eq!(normalize_text("①"), "1");
+    }
+
+    #[test]
+    fn normalize_message_event() {
+        let mut event: Event = value!({
+            "id": "1",
+            "time": 0.0,
+            "type": "message",
+            "detail_type": "private",
+            "sub_type": "",
+            "self": {"platform": "test", "user_id": "bot"},
+            "message_id": "1",
+            "message": [
+                {"type": "text", "data": {"text": "ｈｅｌｌｏ　"}},
+                {"type": "mention", "data": {"user_id": "ｕ"}}
+            ],
+            "alt_message": "",
+            "user_id": "u"
+        })
+        .try_into()
+        .unwrap();
+        normalize_event(&mut event);
+        let segments: Segments = event.extra.get_downcast("message").unwrap();
+        assert_eq!(
+            segments[0].data.get_downcast::<String>("text").unwrap(),
+            "hello "
+        );
+        assert_eq!(
+            segments[1].data.get_downcast::<String>("user_id").unwrap(),
+            "ｕ"
+        );
+        assert!(event
+            .extra
+            .get_downcast::<String>("alt_message")
+            .unwrap()
+            .starts_with("hello "));
+    }
+}
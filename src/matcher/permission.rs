@@ -0,0 +1,124 @@
+use crate::{layered_pre_handle, AsyncRule, MatcherHandler, Rule, Session, Signal};
+use std::{future::Future, pin::Pin};
+use walle_core::prelude::async_trait;
+
+/// 权限层，Rule 不匹配的事件发送者无法使用该 Handler，且在 help 中不可见
+pub struct PermissionLayer<R, H> {
+    pub rule: R,
+    pub handler: H,
+}
+
+#[async_trait]
+impl<R, H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for PermissionLayer<R, H>
+where
+    R: Rule<T, D, S, P, I> + Sync,
+    H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        match self.rule.rule(session) {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & layered_pre_handle(&self.handler, session).await,
+        }
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        if self.rule.rule(session).is_matched() {
+            self.handler.permit(session)
+        } else {
+            Box::pin(async { false })
+        }
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
+    fn handle<'a, 't>(
+        &'a self,
+        session: Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 't>>
+    where
+        'a: 't,
+        Self: 't,
+    {
+        self.handler.handle(session)
+    }
+}
+
+/// 异步权限层，可在鉴权时调用 Action，如查询群成员角色
+pub struct AsyncPermissionLayer<R, H> {
+    pub rule: R,
+    pub handler: H,
+}
+
+#[async_trait]
+impl<R, H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for AsyncPermissionLayer<R, H>
+where
+    R: AsyncRule<T, D, S, P, I>,
+    H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send + Sync,
+    D: Send + Sync,
+    S: Send + Sync,
+    P: Send + Sync,
+    I: Send + Sync,
+{
+    /// 先执行内层 pre_handle，仅在其匹配时才调用异步 Rule，避免无关消息触发 Action
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        match layered_pre_handle(&self.handler, session).await {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => self.rule.rule(session).await & sig,
+        }
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        Box::pin(async move {
+            self.rule.rule(session).await.is_matched() && self.handler.permit(session).await
+        })
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
+    fn handle<'a, 't>(
+        &'a self,
+        session: Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 't>>
+    where
+        'a: 't,
+        Self: 't,
+    {
+        self.handler.handle(session)
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{MatcherHandler, Rule, Session, Signal};
+use crate::{
+    layered_pre_handle, And, AsyncAnd, AsyncNot, AsyncOr, MatcherHandler, Not, Or, Rule, Session,
+    Signal,
+};
+use async_trait::async_trait;
 use std::future::Future;
 use std::pin::Pin;
 
@@ -15,6 +19,7 @@ pub trait PreHandler<T = (), D = (), S = (), P = (), I = ()> {
             before: true,
         }
     }
+    /// 两个 PreHandler 均匹配时匹配，与 `and` 相同
     fn with<PR>(self, pr: PR) -> JoinedPreHandler<Self, PR>
     where
         Self: Sized,
@@ -22,6 +27,30 @@ pub trait PreHandler<T = (), D = (), S = (), P = (), I = ()> {
     {
         JoinedPreHandler(self, pr)
     }
+    /// 两个 PreHandler 均匹配时匹配，左侧不匹配时短路
+    fn and<PR>(self, pr: PR) -> And<Self, PR>
+    where
+        Self: Sized,
+        PR: PreHandler<T, D, S, P, I>,
+    {
+        And(self, pr)
+    }
+    /// 任一 PreHandler 匹配时匹配，左侧匹配时短路
+    fn or<PR>(self, pr: PR) -> Or<Self, PR>
+    where
+        Self: Sized,
+        PR: PreHandler<T, D, S, P, I>,
+    {
+        Or(self, pr)
+    }
+    /// 取反，MatchAndBlock 同样视为匹配
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+    /// Rule 与 PreHandler 均匹配时匹配，先执行 Rule，不匹配时短路
     fn with_rule<R>(self, rule: R) -> JoinedRulePreHandler<R, Self>
     where
         Self: Sized,
@@ -37,18 +66,52 @@ pub struct LayeredPreHandler<PR, H> {
     pub before: bool,
 }
 
+#[async_trait]
 impl<PR, H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for LayeredPreHandler<PR, H>
 where
     PR: PreHandler<T, D, S, P, I> + Sync,
     H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
 {
-    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
         if self.before {
-            self.pre.pre_handle(session) + self.handler.pre_handle(session)
+            match self.pre.pre_handle(session) {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & layered_pre_handle(&self.handler, session).await,
+            }
         } else {
-            self.handler.pre_handle(session) + self.pre.pre_handle(session)
+            match layered_pre_handle(&self.handler, session).await {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & self.pre.pre_handle(session),
+            }
         }
     }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
     fn handle<'a, 't>(
         &'a self,
         session: Session<T, D, S, P, I>,
@@ -69,10 +132,14 @@ where
     PR1: PreHandler<T, D, S, P, I> + Sync,
 {
     fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
-        self.0.pre_handle(session) + self.1.pre_handle(session)
+        match self.0.pre_handle(session) {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & self.1.pre_handle(session),
+        }
     }
 }
 
+/// Rule 与 PreHandler 的逻辑与，第三项为 true 时先执行 PreHandler
 pub struct JoinedRulePreHandler<R, PR>(pub R, pub PR, pub bool);
 
 impl<R, PR, T, D, S, P, I> PreHandler<T, D, S, P, I> for JoinedRulePreHandler<R, PR>
@@ -82,9 +149,15 @@ where
 {
     fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
         if self.2 {
-            self.1.pre_handle(session) + self.0.rule(session)
+            match self.1.pre_handle(session) {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & self.0.rule(session),
+            }
         } else {
-            self.0.rule(session) + self.1.pre_handle(session)
+            match self.0.rule(session) {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & self.1.pre_handle(session),
+            }
         }
     }
 }
@@ -106,3 +179,140 @@ where
 {
     PreHandleFn(pre)
 }
+
+/// 异步 PreHandler，可以在预处理时调用 Action 或读取外部存储
+#[async_trait]
+pub trait AsyncPreHandler<T = (), D = (), S = (), P = (), I = ()>: Sync {
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal;
+    /// 两个 AsyncPreHandler 均匹配时匹配，左侧不匹配时短路
+    fn and<PR>(self, pr: PR) -> AsyncAnd<Self, PR>
+    where
+        Self: Sized,
+        PR: AsyncPreHandler<T, D, S, P, I>,
+    {
+        AsyncAnd(self, pr)
+    }
+    /// 任一 AsyncPreHandler 匹配时匹配，左侧匹配时短路
+    fn or<PR>(self, pr: PR) -> AsyncOr<Self, PR>
+    where
+        Self: Sized,
+        PR: AsyncPreHandler<T, D, S, P, I>,
+    {
+        AsyncOr(self, pr)
+    }
+    /// 取反，MatchAndBlock 同样视为匹配
+    fn not(self) -> AsyncNot<Self>
+    where
+        Self: Sized,
+    {
+        AsyncNot(self)
+    }
+    fn layer<H>(self, handler: H) -> LayeredAsyncPreHandler<Self, H>
+    where
+        Self: Sized,
+        H: MatcherHandler<T, D, S, P, I>,
+    {
+        LayeredAsyncPreHandler {
+            pre: self,
+            handler,
+            before: true,
+        }
+    }
+}
+
+pub struct LayeredAsyncPreHandler<PR, H> {
+    pub pre: PR,
+    pub handler: H,
+    pub before: bool,
+}
+
+#[async_trait]
+impl<PR, H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for LayeredAsyncPreHandler<PR, H>
+where
+    PR: AsyncPreHandler<T, D, S, P, I>,
+    H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        if self.before {
+            match self.pre.pre_handle(session).await {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & layered_pre_handle(&self.handler, session).await,
+            }
+        } else {
+            match layered_pre_handle(&self.handler, session).await {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & self.pre.pre_handle(session).await,
+            }
+        }
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
+    fn handle<'a, 't>(
+        &'a self,
+        session: Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 't>>
+    where
+        'a: 't,
+        Self: 't,
+    {
+        self.handler.handle(session)
+    }
+}
+
+pub struct AsyncPreHandleFn<F>(F);
+
+impl<F, T, D, S, P, I> AsyncPreHandler<T, D, S, P, I> for AsyncPreHandleFn<F>
+where
+    F: for<'a> Fn(
+            &'a mut Session<T, D, S, P, I>,
+        ) -> Pin<Box<dyn Future<Output = Signal> + Send + 'a>>
+        + Sync,
+{
+    fn pre_handle<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.0(session)
+    }
+}
+
+/// `async_pre_handle_fn(|s: &mut Session<Message, Group>| Box::pin(async move { .. }))`
+pub fn async_pre_handle_fn<F, T, D, S, P, I>(pre: F) -> AsyncPreHandleFn<F>
+where
+    F: for<'a> Fn(
+            &'a mut Session<T, D, S, P, I>,
+        ) -> Pin<Box<dyn Future<Output = Signal> + Send + 'a>>
+        + Sync,
+{
+    AsyncPreHandleFn(pre)
+}
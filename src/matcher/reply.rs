@@ -0,0 +1,183 @@
+use crate::{MatcherError, ReplyAbleSession, Session};
+use futures_util::{Stream, StreamExt};
+use std::{fmt::Display, future::Future, pin::Pin};
+use tracing::warn;
+use walle_core::{prelude::MsgSegment, segment::Segments};
+
+/// 可作为 Handler 返回值，并由框架自动回复的类型
+pub trait IntoReply<T = (), D = (), S = (), P = (), I = ()>: Send {
+    fn reply<'a>(
+        self,
+        session: &'a Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>
+    where
+        Self: 'a;
+}
+
+/// 不回复
+impl<T, D, S, P, I> IntoReply<T, D, S, P, I> for () {
+    fn reply<'a>(
+        self,
+        _session: &'a Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>
+    where
+        Self: 'a,
+    {
+        Box::pin(async {})
+    }
+}
+
+macro_rules! impl_message_reply {
+    ($($ty: ty),*) => {
+        $(
+            impl<T, D, S, P, I> IntoReply<T, D, S, P, I> for $ty
+            where
+                Session<T, D, S, P, I>: ReplyAbleSession + Sync,
+            {
+                fn reply<'a>(
+                    self,
+                    session: &'a Session<T, D, S, P, I>,
+                ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>
+                where
+                    Self: 'a,
+                {
+                    Box::pin(async move {
+                        if let Err(e) = session.send(self).await {
+                            warn!(target: "Walle", "reply failed: {}", e);
+                        }
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_message_reply!(String, &'static str, MsgSegment, Segments);
+
+/// None 时不回复
+impl<R, T, D, S, P, I> IntoReply<T, D, S, P, I> for Option<R>
+where
+    R: IntoReply<T, D, S, P, I>,
+{
+    fn reply<'a>(
+        self,
+        session: &'a Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>
+    where
+        Self: 'a,
+    {
+        match self {
+            Some(r) => r.reply(session),
+            None => Box::pin(async {}),
+        }
+    }
+}
+
+/// Err 时通过 Matchers 上报错误，并向用户回复 `MatchersConfig::error_message`
+impl<R, E, T, D, S, P, I> IntoReply<T, D, S, P, I> for Result<R, E>
+where
+    R: IntoReply<T, D, S, P, I>,
+    E: Display + Send,
+    Session<T, D, S, P, I>: Sync,
+{
+    fn reply<'a>(
+        self,
+        session: &'a Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>
+    where
+        Self: 'a,
+    {
+        match self {
+            Ok(r) => r.reply(session),
+            Err(e) => {
+                let error = MatcherError::Failed(e.to_string());
+                Box::pin(async move { session.report(error).await })
+            }
+        }
+    }
+}
+
+/// 依次回复 Stream 中的每一项
+pub struct ReplyStream<St>(pub St);
+
+impl<St, R, T, D, S, P, I> IntoReply<T, D, S, P, I> for ReplyStream<St>
+where
+    St: Stream<Item = R> + Send,
+    R: IntoReply<T, D, S, P, I>,
+    Session<T, D, S, P, I>: Sync,
+{
+    fn reply<'a>(
+        self,
+        session: &'a Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>
+    where
+        Self: 'a,
+    {
+        Box::pin(async move {
+            let mut stream = Box::pin(self.0);
+            while let Some(r) = stream.next().await {
+                r.reply(session).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, DummyCaller};
+    use crate::{MatchersConfig, MatchersHandle, SessionOrigin};
+    use std::sync::Arc;
+    use walle_core::event::{BaseEvent, Message, ParseEvent, Private};
+
+    fn new_session() -> (Arc<DummyCaller>, Session<Message, Private>) {
+        let event = message_event("1", "u", None, "hi");
+        let (dummy, caller) = DummyCaller::arc();
+        let mut session = Session::new(
+            BaseEvent::parse(event.clone(), "dummy").unwrap(),
+            caller,
+            Arc::new(MatchersConfig::default()),
+            MatchersHandle::default(),
+        );
+        session.origin = Some(Arc::new(SessionOrigin {
+            matcher: Some("m".to_string()),
+            event,
+            implt: "dummy".to_string(),
+        }));
+        (dummy, session)
+    }
+
+    async fn replied<R: IntoReply<Message, Private>>(reply: R) -> Vec<String> {
+        let (dummy, session) = new_session();
+        reply.reply(&session).await;
+        dummy.sent_texts()
+    }
+
+    #[tokio::test]
+    async fn message_types_are_sent() {
+        assert_eq!(replied("hi").await, ["hi"]);
+        assert_eq!(replied("hi".to_string()).await, ["hi"]);
+        assert!(replied(()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn option_replies_only_some() {
+        assert_eq!(replied(Some("hi")).await, ["hi"]);
+        assert!(replied(None::<String>).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn result_err_replies_error_message() {
+        assert_eq!(replied(Ok::<_, String>("hi")).await, ["hi"]);
+        assert_eq!(
+            replied(Err::<String, _>("boom")).await,
+            [MatchersConfig::default().error_message]
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_replies_each_item() {
+        let stream = futures_util::stream::iter(vec![Some("a"), None, Some("b")]);
+        assert_eq!(replied(ReplyStream(stream)).await, ["a", "b"]);
+    }
+}
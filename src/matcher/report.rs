@@ -0,0 +1,207 @@
+use crate::{ActionCaller, MatchersConfig};
+use std::{any::Any, fmt, sync::Arc};
+use tracing::error;
+use walle_core::{
+    action::{Action, SendMessage},
+    event::Event,
+    prelude::async_trait,
+    resp::Resp,
+    segment::IntoMessage,
+    structs::Selft,
+    util::{GetSelf, ValueMapExt},
+    WalleResult,
+};
+
+/// Handler 执行时产生的错误
+#[derive(Debug, Clone)]
+pub enum MatcherError {
+    /// Handler 返回的错误
+    Failed(String),
+    /// Handler panic 信息
+    Panicked(String),
+}
+
+impl fmt::Display for MatcherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Failed(e) => write!(f, "failed: {}", e),
+            Self::Panicked(e) => write!(f, "panicked: {}", e),
+        }
+    }
+}
+
+impl MatcherError {
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic".to_string()
+        };
+        Self::Panicked(message)
+    }
+}
+
+/// 上报给 ErrorReporter 的错误信息
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    /// 出错的 Matcher 名称
+    pub matcher: Option<String>,
+    /// 触发 Matcher 的原始事件
+    pub event: Event,
+    pub error: MatcherError,
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Matcher {} {} on event {}",
+            self.matcher.as_deref().unwrap_or("<unnamed>"),
+            self.error,
+            self.event.id
+        )
+    }
+}
+
+/// 错误上报 Hook
+#[async_trait]
+pub trait ErrorReporter: Sync {
+    async fn report(
+        &self,
+        report: &ErrorReport,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        config: &Arc<MatchersConfig>,
+    );
+}
+
+/// 将错误输出到 tracing 日志
+pub struct TracingReporter;
+
+#[async_trait]
+impl ErrorReporter for TracingReporter {
+    async fn report(
+        &self,
+        report: &ErrorReport,
+        _caller: &Arc<dyn ActionCaller + Send + 'static>,
+        _config: &Arc<MatchersConfig>,
+    ) {
+        error!(target: "Walle", "{}", report);
+    }
+}
+
+/// 将错误私聊发送给 `MatchersConfig::superusers`
+pub struct SuperuserReporter;
+
+#[async_trait]
+impl ErrorReporter for SuperuserReporter {
+    async fn report(
+        &self,
+        report: &ErrorReport,
+        caller: &Arc<dyn ActionCaller + Send + 'static>,
+        config: &Arc<MatchersConfig>,
+    ) {
+        let selft = report.event.get_self();
+        for superuser in &config.superusers {
+            let action = SendMessage {
+                detail_type: "private".to_string(),
+                user_id: Some(superuser.clone()),
+                group_id: None,
+                guild_id: None,
+                channel_id: None,
+                message: report.to_string().into_message(),
+            };
+            if let Err(e) = send(caller, selft.clone(), action).await {
+                error!(target: "Walle", "report error to {} failed: {}", superuser, e);
+            }
+        }
+    }
+}
+
+async fn send(
+    caller: &Arc<dyn ActionCaller + Send + 'static>,
+    selft: Selft,
+    action: SendMessage,
+) -> WalleResult<Resp> {
+    let mut action: Action = action.into();
+    action.selft = Some(selft);
+    caller.call_action(action).await
+}
+
+/// 向原始事件所在的会话发送消息
+pub(crate) async fn reply_event(
+    caller: &Arc<dyn ActionCaller + Send + 'static>,
+    event: &Event,
+    message: &str,
+) -> WalleResult<Resp> {
+    let get = |key: &str| event.extra.get_downcast::<String>(key).ok();
+    let action = SendMessage {
+        detail_type: event.detail_type.clone(),
+        user_id: get("user_id"),
+        group_id: get("group_id"),
+        guild_id: get("guild_id"),
+        channel_id: get("channel_id"),
+        message: message.into_message(),
+    };
+    send(caller, event.get_self(), action).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::start_with;
+    use crate::testing::{message_event, DummyCaller};
+    use crate::{handler_fn, MatcherHandlerExt, Matchers, Rule, Session};
+    use std::{sync::Mutex, time::Duration};
+    use walle_core::event::{Message, Private};
+
+    struct Collecting(Arc<Mutex<Vec<ErrorReport>>>);
+
+    #[async_trait]
+    impl ErrorReporter for Collecting {
+        async fn report(
+            &self,
+            report: &ErrorReport,
+            _caller: &Arc<dyn ActionCaller + Send + 'static>,
+            _config: &Arc<MatchersConfig>,
+        ) {
+            self.0.lock().unwrap().push(report.clone());
+        }
+    }
+
+    async fn boom(_: Session<Message, Private>) {
+        panic!("boom");
+    }
+
+    #[tokio::test]
+    async fn panic_is_reported_and_replied() {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let matchers = Matchers::default()
+            .add_matcher(
+                start_with("boom")
+                    .layer(handler_fn(boom))
+                    .boxed()
+                    .name("boom"),
+            )
+            .add_reporter(Collecting(reports.clone()));
+        let (dummy, caller) = DummyCaller::arc();
+        let config = Arc::new(MatchersConfig::default());
+        matchers
+            .handle()
+            .call(&message_event("1", "u", None, "boom"), &config, &caller)
+            .await;
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while dummy.sent_texts().is_empty() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].matcher.as_deref(), Some("boom"));
+        assert!(matches!(&reports[0].error, MatcherError::Panicked(e) if e == "boom"));
+        assert_eq!(dummy.sent_texts(), [config.error_message.as_str()]);
+    }
+}
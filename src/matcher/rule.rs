@@ -1,4 +1,8 @@
-use crate::{JoinedRulePreHandler, MatcherHandler, PreHandler, Session, Signal};
+use crate::{
+    layered_pre_handle, And, AsyncAnd, AsyncNot, AsyncOr, JoinedRulePreHandler, MatcherHandler,
+    Not, Or, PreHandler, Session, Signal,
+};
+use async_trait::async_trait;
 use std::future::Future;
 use std::pin::Pin;
 
@@ -15,6 +19,7 @@ pub trait Rule<T = (), D = (), S = (), P = (), I = ()> {
             before: true,
         }
     }
+    /// 两个 Rule 均匹配时匹配，与 `and` 相同
     fn with<R>(self, rule: R) -> JoinedRule<Self, R>
     where
         Self: Sized,
@@ -22,6 +27,30 @@ pub trait Rule<T = (), D = (), S = (), P = (), I = ()> {
     {
         JoinedRule(self, rule)
     }
+    /// 两个 Rule 均匹配时匹配，左侧不匹配时短路
+    fn and<R>(self, rule: R) -> And<Self, R>
+    where
+        Self: Sized,
+        R: Rule<T, D, S, P, I>,
+    {
+        And(self, rule)
+    }
+    /// 任一 Rule 匹配时匹配，左侧匹配时短路
+    fn or<R>(self, rule: R) -> Or<Self, R>
+    where
+        Self: Sized,
+        R: Rule<T, D, S, P, I>,
+    {
+        Or(self, rule)
+    }
+    /// 取反，MatchAndBlock 同样视为匹配
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+    /// Rule 与 PreHandler 均匹配时匹配，先执行 PreHandler，不匹配时短路
     fn with_pre_handler<PR>(self, pre_handler: PR) -> JoinedRulePreHandler<Self, PR>
     where
         Self: Sized,
@@ -37,18 +66,52 @@ pub struct LayeredRule<R, H> {
     pub before: bool,
 }
 
+#[async_trait]
 impl<R, H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for LayeredRule<R, H>
 where
     R: Rule<T, D, S, P, I> + Sync,
     H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send,
+    D: Send,
+    S: Send,
+    P: Send,
+    I: Send,
 {
-    fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
         if self.before {
-            self.rule.rule(session) + self.handler.pre_handle(session)
+            match self.rule.rule(session) {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & layered_pre_handle(&self.handler, session).await,
+            }
         } else {
-            self.handler.pre_handle(session) + self.rule.rule(session)
+            match layered_pre_handle(&self.handler, session).await {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & self.rule.rule(session),
+            }
         }
     }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
     fn handle<'a, 't>(
         &'a self,
         session: Session<T, D, S, P, I>,
@@ -69,7 +132,10 @@ where
     R1: Rule<T, D, S, P, I> + Sync,
 {
     fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal {
-        self.0.rule(session) + self.1.rule(session)
+        match self.0.rule(session) {
+            Signal::NotMatch => Signal::NotMatch,
+            sig => sig & self.1.rule(session),
+        }
     }
 }
 
@@ -90,3 +156,136 @@ where
 {
     RuleFn(rule)
 }
+
+/// 异步 Rule，可以在匹配前调用 Action 或读取外部存储
+#[async_trait]
+pub trait AsyncRule<T = (), D = (), S = (), P = (), I = ()>: Sync {
+    async fn rule(&self, session: &Session<T, D, S, P, I>) -> Signal;
+    /// 两个 AsyncRule 均匹配时匹配，左侧不匹配时短路
+    fn and<R>(self, rule: R) -> AsyncAnd<Self, R>
+    where
+        Self: Sized,
+        R: AsyncRule<T, D, S, P, I>,
+    {
+        AsyncAnd(self, rule)
+    }
+    /// 任一 AsyncRule 匹配时匹配，左侧匹配时短路
+    fn or<R>(self, rule: R) -> AsyncOr<Self, R>
+    where
+        Self: Sized,
+        R: AsyncRule<T, D, S, P, I>,
+    {
+        AsyncOr(self, rule)
+    }
+    /// 取反，MatchAndBlock 同样视为匹配
+    fn not(self) -> AsyncNot<Self>
+    where
+        Self: Sized,
+    {
+        AsyncNot(self)
+    }
+    fn layer<H>(self, handler: H) -> LayeredAsyncRule<Self, H>
+    where
+        Self: Sized,
+        H: MatcherHandler<T, D, S, P, I>,
+    {
+        LayeredAsyncRule {
+            rule: self,
+            handler,
+            before: true,
+        }
+    }
+}
+
+pub struct LayeredAsyncRule<R, H> {
+    pub rule: R,
+    pub handler: H,
+    pub before: bool,
+}
+
+#[async_trait]
+impl<R, H, T, D, S, P, I> MatcherHandler<T, D, S, P, I> for LayeredAsyncRule<R, H>
+where
+    R: AsyncRule<T, D, S, P, I>,
+    H: MatcherHandler<T, D, S, P, I> + Sync,
+    T: Send + Sync,
+    D: Send + Sync,
+    S: Send + Sync,
+    P: Send + Sync,
+    I: Send + Sync,
+{
+    async fn pre_handle(&self, session: &mut Session<T, D, S, P, I>) -> Signal {
+        if self.before {
+            match self.rule.rule(session).await {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & layered_pre_handle(&self.handler, session).await,
+            }
+        } else {
+            match layered_pre_handle(&self.handler, session).await {
+                Signal::NotMatch => Signal::NotMatch,
+                sig => sig & self.rule.rule(session).await,
+            }
+        }
+    }
+    fn permit<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = bool> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.permit(session)
+    }
+    fn prepare<'a, 'b, 't>(
+        &'a self,
+        session: &'b mut Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.handler.prepare(session)
+    }
+    fn handle<'a, 't>(
+        &'a self,
+        session: Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 't>>
+    where
+        'a: 't,
+        Self: 't,
+    {
+        self.handler.handle(session)
+    }
+}
+
+pub struct AsyncRuleFn<F>(F);
+
+impl<F, T, D, S, P, I> AsyncRule<T, D, S, P, I> for AsyncRuleFn<F>
+where
+    F: for<'a> Fn(&'a Session<T, D, S, P, I>) -> Pin<Box<dyn Future<Output = Signal> + Send + 'a>>
+        + Sync,
+{
+    fn rule<'a, 'b, 't>(
+        &'a self,
+        session: &'b Session<T, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        self.0(session)
+    }
+}
+
+/// `async_rule_fn(|s: &Session<Message, Group>| Box::pin(async move { .. }))`
+pub fn async_rule_fn<F, T, D, S, P, I>(rule: F) -> AsyncRuleFn<F>
+where
+    F: for<'a> Fn(&'a Session<T, D, S, P, I>) -> Pin<Box<dyn Future<Output = Signal> + Send + 'a>>
+        + Sync,
+{
+    AsyncRuleFn(rule)
+}
@@ -1,25 +1,68 @@
-use super::TempMatcher;
-use crate::{ActionCaller, ActionCallerExt, MatcherHandlerExt, MatchersConfig, TempMatchers};
-use std::{sync::Arc, time::Duration};
+use super::temp::{TempMatcher, WaitGuard};
+use crate::{
+    ActionCaller, ActionCallerExt, ErrorReport, Matcher, MatcherError, MatcherHandlerExt,
+    MatchersConfig, MatchersHandle, PendingWait, WaitError, TEMP_PRIORITY,
+};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::mpsc::UnboundedReceiver;
 use walle_core::{
     action::SendMessage,
     event::{
-        BaseEvent, Group, ImplLevel, Message, MessageDeatilTypes, PlatformLevel, Private,
-        SubTypeLevel, TryFromEvent,
+        BaseEvent, Event, Group, ImplLevel, Message, MessageDeatilTypes, PlatformLevel, Private,
+        SubTypeLevel,
     },
-    prelude::async_trait,
+    prelude::{async_trait, PushToValueMap, ToEvent, TryFromEvent, TryFromValue},
     segment::{IntoMessage, Segments},
     structs::SendMessageResp,
-    WalleError, WalleResult,
+    WalleResult,
 };
 
+/// Session 的来源，用于错误上报
+#[derive(Debug, Clone)]
+pub struct SessionOrigin {
+    pub matcher: Option<String>,
+    pub event: Event,
+    /// 解析事件时使用的实现名，用于重新解析事件
+    pub implt: String,
+}
+
+/// Session 扩展数据，用于在 PreHandler 与 Handler 之间传递数据，每种类型保存一个值
+#[derive(Clone, Default)]
+pub struct Extensions(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl Extensions {
+    pub fn insert<E: Any + Send + Sync>(&mut self, value: E) {
+        self.0.insert(TypeId::of::<E>(), Arc::new(value));
+    }
+    pub fn get<E: Any + Send + Sync>(&self) -> Option<&E> {
+        self.0
+            .get(&TypeId::of::<E>())
+            .and_then(|value| value.downcast_ref())
+    }
+    pub fn remove<E: Any + Send + Sync>(&mut self) -> bool {
+        self.0.remove(&TypeId::of::<E>()).is_some()
+    }
+    /// 移除并取回值，值仍被克隆出的 Session 共享时返回 None
+    pub fn take<E: Any + Send + Sync>(&mut self) -> Option<E> {
+        let value = self.0.remove(&TypeId::of::<E>())?.downcast().ok()?;
+        Arc::try_unwrap(value).ok()
+    }
+}
+
 /// Matcher 使用的 Session
 #[derive(Clone)]
 pub struct Session<T = (), D = (), S = (), P = (), I = ()> {
     pub event: BaseEvent<T, D, S, P, I>,
     pub config: Arc<MatchersConfig>,
     pub caller: Arc<dyn ActionCaller + Send + 'static>,
-    temps: TempMatchers,
+    pub matchers: MatchersHandle,
+    pub origin: Option<Arc<SessionOrigin>>,
+    pub extensions: Extensions,
 }
 
 impl<T, D, S, P, I> Session<T, D, S, P, I> {
@@ -27,13 +70,31 @@ impl<T, D, S, P, I> Session<T, D, S, P, I> {
         event: BaseEvent<T, D, S, P, I>,
         caller: Arc<dyn ActionCaller + Send + 'static>,
         config: Arc<MatchersConfig>,
-        temps: TempMatchers,
+        matchers: MatchersHandle,
     ) -> Self {
         Self {
             event,
             config,
             caller,
-            temps,
+            matchers,
+            origin: None,
+            extensions: Extensions::default(),
+        }
+    }
+    /// 通过 Matchers 上报 Handler 错误
+    pub async fn report(&self, error: MatcherError) {
+        match &self.origin {
+            Some(origin) => {
+                let report = ErrorReport {
+                    matcher: origin.matcher.clone(),
+                    event: origin.event.clone(),
+                    error,
+                };
+                self.matchers
+                    .report(report, &self.caller, &self.config)
+                    .await
+            }
+            None => tracing::error!(target: "Walle", "Matcher {}", error),
         }
     }
 }
@@ -56,11 +117,73 @@ pub trait ReplyAbleSession {
         &self,
         message: M,
     ) -> WalleResult<SendMessageResp>;
+    /// 发送提问并等待同一用户的下一条消息，默认等待 30 秒，
+    /// 可通过 `MatchersHandle::cancel_waits` 取消
     async fn get<M: IntoMessage + Send + 'static>(
         &mut self,
         message: M,
         timeout: Option<Duration>,
-    ) -> WalleResult<()>;
+    ) -> Result<(), WaitError>;
+}
+
+/// 频道消息，walle-core 暂未提供该 detail_type
+#[derive(Debug, Clone, PartialEq, TryFromValue, PushToValueMap, ToEvent, TryFromEvent)]
+#[event(detail_type)]
+pub struct Channel {
+    pub guild_id: String,
+    pub channel_id: String,
+}
+
+impl<D, S, P, I> Session<Message, D, S, P, I>
+where
+    Self: ReplyAbleSession + Send,
+    D: Send + 'static,
+    S: Send + 'static,
+    P: Send + 'static,
+    I: Send + 'static,
+{
+    /// 注册临时 Matcher 并发送提问，等待临时 Matcher 收到的下一条消息
+    ///
+    /// 收到取消关键词、等待被取消或超时时返回错误，返回或被丢弃时都会移除临时 Matcher
+    async fn wait_reply(
+        &mut self,
+        temp: Matcher,
+        mut rx: UnboundedReceiver<BaseEvent<Message, D, S, P, I>>,
+        message: Segments,
+        duration: Option<Duration>,
+    ) -> Result<(), WaitError> {
+        let duration = duration.unwrap_or(Duration::from_secs(30));
+        let wait = PendingWait::new(
+            &self.event.id,
+            &self.event.ty.user_id,
+            self.origin
+                .as_ref()
+                .and_then(|origin| origin.matcher.clone()),
+            duration,
+        );
+        let _guard = WaitGuard {
+            matchers: self.matchers.clone(),
+            id: wait.id.clone(),
+        };
+        self.matchers
+            .insert(
+                temp.name(&wait.id)
+                    .priority(TEMP_PRIORITY)
+                    .ttl(duration)
+                    .times(1),
+            )
+            .await;
+        self.matchers.add_pending(wait);
+        self.send(message).await?;
+        match tokio::time::timeout(duration, rx.recv()).await {
+            Ok(Some(event)) => {
+                self.event = event;
+                Ok(())
+            }
+            Ok(None) => Err(WaitError::Cancelled),
+            Err(_) => Err(WaitError::Timeout),
+        }
+    }
 }
 
 impl<S, P, I> Session<Message, Private, S, P, I>
@@ -69,36 +192,128 @@ where
     P: Sync,
     I: Sync,
 {
-    pub async fn send(&self, message: Segments) -> WalleResult<SendMessageResp> {
+    pub async fn send<M: IntoMessage>(&self, message: M) -> WalleResult<SendMessageResp> {
         self.call(SendMessage {
             detail_type: "private".to_string(),
             user_id: Some(self.event.ty.user_id.clone()),
             group_id: None,
             channel_id: None,
             guild_id: None,
-            message,
+            message: message.into_message(),
         })
         .await
     }
 }
 
+#[async_trait]
+impl<S, P, I> ReplyAbleSession for Session<Message, Private, S, P, I>
+where
+    S: TryFromEvent<SubTypeLevel> + Send + Sync + 'static,
+    P: TryFromEvent<PlatformLevel> + Send + Sync + 'static,
+    I: TryFromEvent<ImplLevel> + Send + Sync + 'static,
+{
+    async fn send<M: IntoMessage + Send + 'static>(
+        &self,
+        message: M,
+    ) -> WalleResult<SendMessageResp> {
+        self.send(message).await
+    }
+    async fn get<M>(&mut self, message: M, duration: Option<Duration>) -> Result<(), WaitError>
+    where
+        M: IntoMessage + Send + 'static,
+    {
+        use crate::builtin::user_id_check;
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let temp = TempMatcher { tx }
+            .with_rule(user_id_check(&self.event.ty.user_id))
+            .boxed();
+        self.wait_reply(temp, rx, message.into_message(), duration)
+            .await
+    }
+}
+
 impl<S, P, I> Session<Message, Group, S, P, I>
 where
     S: Sync,
     P: Sync,
     I: Sync,
 {
-    pub async fn send(&self, message: Segments) -> WalleResult<SendMessageResp> {
+    pub async fn send<M: IntoMessage>(&self, message: M) -> WalleResult<SendMessageResp> {
         self.call(SendMessage {
             detail_type: "group".to_string(),
             user_id: Some(self.event.ty.user_id.clone()),
             group_id: Some(self.event.detail_type.group_id.clone()),
             channel_id: None,
             guild_id: None,
-            message,
+            message: message.into_message(),
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl<S, P, I> ReplyAbleSession for Session<Message, Group, S, P, I>
+where
+    S: TryFromEvent<SubTypeLevel> + Send + Sync + 'static,
+    P: TryFromEvent<PlatformLevel> + Send + Sync + 'static,
+    I: TryFromEvent<ImplLevel> + Send + Sync + 'static,
+{
+    async fn send<M: IntoMessage + Send + 'static>(
+        &self,
+        message: M,
+    ) -> WalleResult<SendMessageResp> {
+        self.send(message).await
+    }
+    async fn get<M>(&mut self, message: M, duration: Option<Duration>) -> Result<(), WaitError>
+    where
+        M: IntoMessage + Send + 'static,
+    {
+        use crate::builtin::{group_id_check, user_id_check};
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let temp = TempMatcher { tx }
+            .with_rule(user_id_check(&self.event.ty.user_id))
+            .with_rule(group_id_check(&self.event.detail_type.group_id))
+            .boxed();
+        self.wait_reply(temp, rx, message.into_message(), duration)
+            .await
+    }
+}
+
+#[async_trait]
+impl<S, P, I> ReplyAbleSession for Session<Message, Channel, S, P, I>
+where
+    S: TryFromEvent<SubTypeLevel> + Send + Sync + 'static,
+    P: TryFromEvent<PlatformLevel> + Send + Sync + 'static,
+    I: TryFromEvent<ImplLevel> + Send + Sync + 'static,
+{
+    async fn send<M: IntoMessage + Send + 'static>(
+        &self,
+        message: M,
+    ) -> WalleResult<SendMessageResp> {
+        self.call(SendMessage {
+            detail_type: "channel".to_string(),
+            user_id: Some(self.event.ty.user_id.clone()),
+            group_id: None,
+            channel_id: Some(self.event.detail_type.channel_id.clone()),
+            guild_id: Some(self.event.detail_type.guild_id.clone()),
+            message: message.into_message(),
         })
         .await
     }
+    async fn get<M>(&mut self, message: M, duration: Option<Duration>) -> Result<(), WaitError>
+    where
+        M: IntoMessage + Send + 'static,
+    {
+        use crate::builtin::{channel_id_check, user_id_check};
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let channel = &self.event.detail_type;
+        let temp = TempMatcher { tx }
+            .with_rule(user_id_check(&self.event.ty.user_id))
+            .with_rule(channel_id_check(&channel.guild_id, &channel.channel_id))
+            .boxed();
+        self.wait_reply(temp, rx, message.into_message(), duration)
+            .await
+    }
 }
 
 #[async_trait]
@@ -130,30 +345,101 @@ where
         })
         .await
     }
-    async fn get<M>(&mut self, message: M, duration: Option<Duration>) -> WalleResult<()>
+    async fn get<M>(&mut self, message: M, duration: Option<Duration>) -> Result<(), WaitError>
     where
         M: IntoMessage + Send + 'static,
     {
-        use crate::builtin::{group_id_check, user_id_check};
-        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        use crate::builtin::{group_id_check, private_only, user_id_check};
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
         let temp = TempMatcher { tx }.with_rule(user_id_check(&self.event.ty.user_id));
-        let mut temps = self.temps.lock().await;
-        if let MessageDeatilTypes::Group(group) = &self.event.detail_type {
-            temps.insert(
-                self.event.id.clone(),
-                temp.with_rule(group_id_check(&group.group_id)).boxed(),
-            );
+        let temp = if let MessageDeatilTypes::Group(group) = &self.event.detail_type {
+            temp.with_rule(group_id_check(&group.group_id)).boxed()
         } else {
-            temps.insert(self.event.id.clone(), temp.boxed());
-        }
-        self.send(message.into_message()).await?;
-        match tokio::time::timeout(duration.unwrap_or(Duration::from_secs(30)), rx.recv()).await {
-            Ok(Some(event)) => {
-                self.event = event;
-                Ok(())
-            }
-            Ok(None) => Err(WalleError::Other("unexpected tx drop".to_string())),
-            Err(e) => Err(WalleError::Other(e.to_string())),
-        }
+            temp.with_rule(private_only()).boxed()
+        };
+        self.wait_reply(temp, rx, message.into_message(), duration)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{message_event, DummyCaller};
+    use walle_core::{event::ParseEvent, util::ValueMapExt};
+
+    fn new_session<D, S, P, I>(event: Event) -> (Arc<DummyCaller>, Session<Message, D, S, P, I>)
+    where
+        D: TryFromEvent<walle_core::event::DetailTypeLevel>,
+        S: TryFromEvent<SubTypeLevel>,
+        P: TryFromEvent<PlatformLevel>,
+        I: TryFromEvent<ImplLevel>,
+    {
+        let (dummy, caller) = DummyCaller::arc();
+        let session = Session::new(
+            BaseEvent::parse(event, "dummy").unwrap(),
+            caller,
+            Arc::new(MatchersConfig::default()),
+            MatchersHandle::default(),
+        );
+        (dummy, session)
+    }
+
+    /// 最后一次 `send_message` 的目标字段
+    fn target(dummy: &DummyCaller) -> [Option<String>; 5] {
+        let actions = dummy.actions.lock().unwrap();
+        let params = &actions.last().unwrap().params;
+        [
+            "detail_type",
+            "user_id",
+            "group_id",
+            "guild_id",
+            "channel_id",
+        ]
+        .map(|key| params.get_downcast::<String>(key).ok())
+    }
+
+    #[tokio::test]
+    async fn channel_reply_targets_channel() {
+        let mut event = message_event("1", "u", None, "hi");
+        event.detail_type = "channel".to_string();
+        event.extra.insert("guild_id".to_string(), "gd".into());
+        event.extra.insert("channel_id".to_string(), "ch".into());
+        let (dummy, session) = new_session::<Channel, (), (), ()>(event);
+        ReplyAbleSession::send(&session, "pong").await.unwrap();
+        assert_eq!(
+            target(&dummy),
+            [
+                Some("channel".to_string()),
+                Some("u".to_string()),
+                None,
+                Some("gd".to_string()),
+                Some("ch".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn inherent_send_targets_chat() {
+        let (dummy, session) =
+            new_session::<Private, (), (), ()>(message_event("1", "u", None, "hi"));
+        session.send(session.message().clone()).await.unwrap();
+        assert_eq!(
+            target(&dummy)[..3],
+            [Some("private".to_string()), Some("u".to_string()), None]
+        );
+
+        let (dummy, session) =
+            new_session::<Group, (), (), ()>(message_event("1", "u", Some("g"), "hi"));
+        session.send("pong").await.unwrap();
+        assert_eq!(
+            target(&dummy)[..3],
+            [
+                Some("group".to_string()),
+                Some("u".to_string()),
+                Some("g".to_string())
+            ]
+        );
+        assert_eq!(dummy.sent_texts(), ["pong"]);
     }
 }
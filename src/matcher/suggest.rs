@@ -0,0 +1,91 @@
+use crate::MatchersConfig;
+
+/// 编辑距离
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(cur).min(row[j]) + 1
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// 从消息中取出以非空命令起始符开头的命令词，返回起始符与命令词
+pub(crate) fn unknown_command<'a>(
+    alt_message: &'a str,
+    config: &'a MatchersConfig,
+) -> Option<(&'a str, &'a str)> {
+    let text = alt_message.trim_start();
+    let start = config
+        .command_start
+        .iter()
+        .filter(|start| !start.is_empty() && text.starts_with(start.as_str()))
+        .max_by_key(|start| start.len())?;
+    let word = text[start.len()..].split_whitespace().next()?;
+    Some((start, word))
+}
+
+/// 按编辑距离返回与 `word` 最接近的至多 3 个命令
+pub(crate) fn suggest<'a>(word: &str, commands: impl Iterator<Item = &'a String>) -> Vec<&'a str> {
+    let max = (word.chars().count() / 3).max(1);
+    let mut candidates: Vec<(usize, &str)> = commands
+        .map(|command| (levenshtein(word, command), command.as_str()))
+        .filter(|(distance, _)| *distance > 0 && *distance <= max)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, command)| command)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein("echo", "echo"), 0);
+        assert_eq!(levenshtein("ehco", "echo"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("复读机", "复读"), 1);
+    }
+
+    #[test]
+    fn suggest_closest_commands() {
+        let commands: Vec<String> = ["echo", "help", "enable", "disable", "echo2"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(suggest("ecbo", commands.iter()), ["echo"]);
+        // 交换相邻字符的距离为 2，超出短命令的容许距离
+        assert!(suggest("ecoh", commands.iter()).is_empty());
+        assert_eq!(suggest("eccho", commands.iter()), ["echo"]);
+        assert_eq!(suggest("echo3", commands.iter()), ["echo", "echo2"]);
+        assert!(suggest("weather", commands.iter()).is_empty());
+    }
+
+    #[test]
+    fn unknown_command_requires_start() {
+        let config = MatchersConfig {
+            command_start: vec!["".to_string(), "/".to_string(), "//".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(unknown_command(" /ecoh hi", &config), Some(("/", "ecoh")));
+        assert_eq!(unknown_command("//ecoh", &config), Some(("//", "ecoh")));
+        assert_eq!(unknown_command("ecoh", &config), None);
+        assert_eq!(unknown_command("/", &config), None);
+    }
+}
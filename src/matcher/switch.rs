@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+use walle_core::{event::Event, util::ValueMapExt, WalleError, WalleResult};
+
+/// Matcher 开关作用域
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SwitchScope {
+    Group(String),
+    User(String),
+    Channel {
+        guild_id: String,
+        channel_id: String,
+    },
+}
+
+impl SwitchScope {
+    pub(crate) fn key(&self) -> String {
+        match self {
+            Self::Group(group_id) => format!("group:{}", group_id),
+            Self::User(user_id) => format!("user:{}", user_id),
+            Self::Channel {
+                guild_id,
+                channel_id,
+            } => format!("channel:{}:{}", guild_id, channel_id),
+        }
+    }
+    /// 事件所属的全部作用域
+    pub fn from_event(event: &Event) -> Vec<Self> {
+        let get = |key: &str| event.extra.get_downcast::<String>(key).ok();
+        let mut scopes = vec![];
+        if let Some(user_id) = get("user_id") {
+            scopes.push(Self::User(user_id));
+        }
+        if let Some(group_id) = get("group_id") {
+            scopes.push(Self::Group(group_id));
+        }
+        if let (Some(guild_id), Some(channel_id)) = (get("guild_id"), get("channel_id")) {
+            scopes.push(Self::Channel {
+                guild_id,
+                channel_id,
+            });
+        }
+        scopes
+    }
+}
+
+impl std::fmt::Display for SwitchScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Group(group_id) => write!(f, "群 {}", group_id),
+            Self::User(user_id) => write!(f, "用户 {}", user_id),
+            Self::Channel {
+                guild_id,
+                channel_id,
+            } => write!(f, "频道 {}/{}", guild_id, channel_id),
+        }
+    }
+}
+
+/// Matcher 开关，记录各 Matcher 被禁用的作用域
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatcherSwitches {
+    disabled: HashMap<String, HashSet<String>>,
+    /// 加载时的文件路径，`persist` 写回该文件
+    #[serde(skip)]
+    path: String,
+}
+
+impl MatcherSwitches {
+    /// 任一作用域禁用该 Matcher 时返回 false
+    pub fn is_enabled(&self, name: &str, scopes: &[SwitchScope]) -> bool {
+        match self.disabled.get(name) {
+            Some(disabled) => !scopes.iter().any(|scope| disabled.contains(&scope.key())),
+            None => true,
+        }
+    }
+    /// 状态发生变化时返回 true
+    pub fn enable(&mut self, name: &str, scope: &SwitchScope) -> bool {
+        let Some(disabled) = self.disabled.get_mut(name) else {
+            return false;
+        };
+        let changed = disabled.remove(&scope.key());
+        if disabled.is_empty() {
+            self.disabled.remove(name);
+        }
+        changed
+    }
+    /// 状态发生变化时返回 true
+    pub fn disable(&mut self, name: &str, scope: &SwitchScope) -> bool {
+        self.disabled
+            .entry(name.to_string())
+            .or_default()
+            .insert(scope.key())
+    }
+    /// 文件不存在时返回空开关，之后 `persist` 会创建该文件
+    pub async fn load(path: &str) -> WalleResult<Self> {
+        let mut switches: Self = match File::open(path).await {
+            Ok(mut file) => {
+                let mut data = String::new();
+                file.read_to_string(&mut data)
+                    .await
+                    .map_err(|e| WalleError::Other(e.to_string()))?;
+                serde_json::from_str(&data).map_err(|e| WalleError::Other(e.to_string()))?
+            }
+            Err(_) => Self::default(),
+        };
+        switches.path = path.to_string();
+        Ok(switches)
+    }
+    pub async fn save(&self, path: &str) -> WalleResult<()> {
+        write_file(path, &self.to_json()?).await
+    }
+    fn to_json(&self) -> WalleResult<String> {
+        serde_json::to_string(self).map_err(|e| WalleError::Other(e.to_string()))
+    }
+    /// 写回加载时的文件，未从文件加载时不持久化
+    pub async fn persist(&self) -> WalleResult<()> {
+        if self.path.is_empty() {
+            return Ok(());
+        }
+        self.save(&self.path).await
+    }
+    /// 加载时的文件路径与序列化后的开关，供释放锁后再写入文件，未从文件加载时为 None
+    pub(crate) fn persist_data(&self) -> WalleResult<Option<(String, String)>> {
+        if self.path.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some((self.path.clone(), self.to_json()?)))
+    }
+}
+
+pub(crate) async fn write_file(path: &str, data: &str) -> WalleResult<()> {
+    let mut file = File::create(path)
+        .await
+        .map_err(|e| WalleError::Other(e.to_string()))?;
+    file.write_all(data.as_bytes())
+        .await
+        .map_err(|e| WalleError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn persist_to_loaded_path() {
+        let path = std::env::temp_dir().join(format!("walle_switch_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let scope = SwitchScope::Group("g".to_string());
+        let mut switches = MatcherSwitches::load(path).await.unwrap();
+        assert!(switches.disable("echo", &scope));
+        switches.persist().await.unwrap();
+        let loaded = MatcherSwitches::load(path).await.unwrap();
+        assert!(!loaded.is_enabled("echo", &[scope]));
+        tokio::fs::remove_file(path).await.unwrap();
+        // 未从文件加载的开关不会写入任何文件
+        MatcherSwitches::default().persist().await.unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{builtin::is_cancel_keyword, MatcherHandler, MatchersHandle, Session, Signal};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::UnboundedSender;
+use walle_core::{
+    event::{BaseEvent, Message},
+    prelude::async_trait,
+    WalleError,
+};
+
+/// `ReplyAbleSession::get` 未收到回复的原因
+#[derive(Debug)]
+pub enum WaitError {
+    /// 等待回复超时
+    Timeout,
+    /// 回复为取消关键词、等待被取消或临时 Matcher 被移除
+    Cancelled,
+    /// 发送提问失败
+    Walle(WalleError),
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "wait timeout"),
+            Self::Cancelled => write!(f, "wait cancelled"),
+            Self::Walle(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+impl From<WalleError> for WaitError {
+    fn from(e: WalleError) -> Self {
+        Self::Walle(e)
+    }
+}
+
+/// 便于在返回 `WalleResult` 的 Handler 中使用 `?`
+impl From<WaitError> for WalleError {
+    fn from(e: WaitError) -> Self {
+        match e {
+            WaitError::Timeout => WalleError::ResponseTimeout,
+            WaitError::Cancelled => WalleError::Other(e.to_string()),
+            WaitError::Walle(e) => e,
+        }
+    }
+}
+
+static NEXT_WAIT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 将下一条匹配的消息转发给等待中的 Session
+///
+/// 消息为取消关键词时取消发送者所有等待中的对话，与全局的 `builtin::cancel` 行为一致
+pub(crate) struct TempMatcher<D, S, P, I> {
+    pub tx: UnboundedSender<BaseEvent<Message, D, S, P, I>>,
+}
+
+#[async_trait]
+impl<D, S, P, I> MatcherHandler<Message, D, S, P, I> for TempMatcher<D, S, P, I>
+where
+    D: Send + 'static,
+    S: Send + 'static,
+    P: Send + 'static,
+    I: Send + 'static,
+{
+    fn pre_handle<'a, 'b, 't>(
+        &'a self,
+        _session: &'b mut Session<Message, D, S, P, I>,
+    ) -> Pin<Box<dyn Future<Output = Signal> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        // 等待方已放弃时不再拦截消息
+        let signal = if self.tx.is_closed() {
+            Signal::NotMatch
+        } else {
+            Signal::Matched
+        };
+        Box::pin(async move { signal })
+    }
+    async fn handle(&self, session: Session<Message, D, S, P, I>) {
+        if is_cancel_keyword(&session) {
+            // 临时 Matcher 被移除后 tx 随之释放，等待方收到 `WaitError::Cancelled`
+            session
+                .matchers
+                .cancel_waits(&session.event.ty.user_id)
+                .await;
+        } else {
+            self.tx.send(session.event).ok();
+        }
+    }
+}
+
+/// 等待中的临时 Matcher
+#[derive(Debug, Clone)]
+pub struct PendingWait {
+    /// 临时 Matcher 名
+    pub id: String,
+    /// 等待回复的用户
+    pub user_id: String,
+    /// 发起等待的 Matcher
+    pub matcher: Option<String>,
+    pub since: Instant,
+    pub timeout: Duration,
+}
+
+impl PendingWait {
+    pub(crate) fn new(
+        event_id: &str,
+        user_id: &str,
+        matcher: Option<String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            id: format!(
+                "temp:{}:{}",
+                event_id,
+                NEXT_WAIT_ID.fetch_add(1, Ordering::Relaxed)
+            ),
+            user_id: user_id.to_string(),
+            matcher,
+            since: Instant::now(),
+            timeout,
+        }
+    }
+}
+
+/// 登记中的等待，释放时移除登记与对应的临时 Matcher，
+/// 因此超时或等待的 Future 被丢弃时都不会遗留临时 Matcher
+pub(crate) struct WaitGuard {
+    pub matchers: MatchersHandle,
+    pub id: String,
+}
+
+impl Drop for WaitGuard {
+    fn drop(&mut self) {
+        self.matchers.remove_pending(&self.id);
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let matchers = self.matchers.clone();
+            let id = std::mem::take(&mut self.id);
+            runtime.spawn(async move {
+                matchers.remove(&id).await;
+            });
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! 单元测试使用的 ActionCaller 与事件构造
+
+use crate::{ActionCaller, Bot, MatchersConfig, MatchersHandle, Session};
+use std::sync::{Arc, Mutex};
+use walle_core::{
+    action::Action,
+    event::{
+        BaseEvent, DetailTypeLevel, Event, ImplLevel, ParseEvent, PlatformLevel, SubTypeLevel,
+        TypeLevel,
+    },
+    prelude::{async_trait, GetSelfs, TryFromEvent},
+    resp::Resp,
+    structs::Selft,
+    value, value_map, WalleResult,
+};
+
+/// 记录所有 Action 的 ActionCaller，未设置 `resp` 时总是返回成功
+#[derive(Default)]
+pub(crate) struct DummyCaller {
+    pub actions: Mutex<Vec<Action>>,
+    pub resp: Mutex<Option<Resp>>,
+}
+
+impl DummyCaller {
+    pub fn arc() -> (Arc<Self>, Arc<dyn ActionCaller + Send + 'static>) {
+        let caller = Arc::new(Self::default());
+        (caller.clone(), caller)
+    }
+    /// 所有 `send_message` 中文本段拼接成的文本
+    pub fn sent_texts(&self) -> Vec<String> {
+        self.actions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|action| action.action == "send_message")
+            .map(|action| {
+                action
+                    .params
+                    .get("message")
+                    .and_then(|message| message.as_list())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|seg| seg.as_map()?.get("data")?.as_map()?.get("text")?.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl GetSelfs for DummyCaller {
+    async fn get_selfs(&self) -> Vec<Selft> {
+        vec![]
+    }
+    async fn get_impl(&self, _: &Selft) -> String {
+        // 让出执行权，使并发事件的分发相互交错
+        tokio::task::yield_now().await;
+        "dummy".to_string()
+    }
+}
+
+#[async_trait]
+impl ActionCaller for DummyCaller {
+    async fn call_action(&self, action: Action) -> WalleResult<Resp> {
+        self.actions.lock().unwrap().push(action);
+        if let Some(resp) = self.resp.lock().unwrap().clone() {
+            return Ok(resp);
+        }
+        Ok(value_map! {"message_id": "1", "time": 0.0}.into())
+    }
+    async fn get_bots(&self) -> Vec<Bot> {
+        vec![]
+    }
+}
+
+/// 构造文本消息事件，`group_id` 为 None 时为私聊
+pub(crate) fn message_event(id: &str, user_id: &str, group_id: Option<&str>, text: &str) -> Event {
+    let mut v = value!({
+        "id": id,
+        "time": 0.0,
+        "type": "message",
+        "detail_type": "private",
+        "sub_type": "",
+        "self": {"platform": "test", "user_id": "bot"},
+        "message_id": id,
+        "message": [{"type": "text", "data": {"text": text}}],
+        "alt_message": text,
+        "user_id": user_id
+    });
+    if let Some(group_id) = group_id {
+        let map = v.as_map_mut().unwrap();
+        map.insert("detail_type".to_string(), "group".into());
+        map.insert("group_id".to_string(), group_id.into());
+    }
+    v.try_into().unwrap()
+}
+
+/// 以 `DummyCaller` 和默认配置构造 Session
+pub(crate) fn session<T, D, S, P, I>(event: Event) -> Session<T, D, S, P, I>
+where
+    T: TryFromEvent<TypeLevel>,
+    D: TryFromEvent<DetailTypeLevel>,
+    S: TryFromEvent<SubTypeLevel>,
+    I: TryFromEvent<ImplLevel>,
+    P: TryFromEvent<PlatformLevel>,
+{
+    let (_, caller) = DummyCaller::arc();
+    Session::new(
+        BaseEvent::parse(event, "dummy").unwrap(),
+        caller,
+        std::sync::Arc::new(MatchersConfig::default()),
+        MatchersHandle::default(),
+    )
+}